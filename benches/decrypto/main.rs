@@ -0,0 +1,55 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use qrcdownloader::decrypto::{decrypt_lyrics, encrypt_lyrics, hex_string_to_byte_array};
+
+mod baseline;
+
+const ITERATIONS: u32 = 200;
+
+fn sample_lyrics(lines: u64) -> String {
+    (0..lines)
+        .map(|i| {
+            let start = i * 3000;
+            format!("[{},3000]歌({},500)词({},500)第({},500){}({},500)行({},1000)\n", start, start, start + 500, start + 1000, i, start + 1500, start + 2000)
+        })
+        .collect()
+}
+
+fn time(mut f: impl FnMut()) -> Duration {
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn bench(name: &str, bytes: usize, baseline: impl FnMut(), current: impl FnMut()) {
+    let throughput = |per_iter: Duration| bytes as f64 / per_iter.max(Duration::from_nanos(1)).as_secs_f64() / 1024.0 / 1024.0;
+    let old = time(baseline);
+    let new = time(current);
+    println!("{:<16} 逐位实现 {:>10.1?}/iter {:>8.2} MiB/s", name, old, throughput(old));
+    println!("{:<16} 查表实现 {:>10.1?}/iter {:>8.2} MiB/s  ({:.2}x)", name, new, throughput(new), old.as_secs_f64() / new.as_secs_f64().max(1e-9));
+}
+
+fn main() {
+    let plain = sample_lyrics(2000);
+    let encrypted = encrypt_lyrics(&plain).expect("加密失败");
+    let ciphertext = hex_string_to_byte_array(&encrypted).expect("十六进制解析失败");
+
+    assert_eq!(baseline::encrypt_lyrics(&plain), encrypted, "查表实现的密文与逐位实现不一致");
+    assert_eq!(baseline::decrypt_lyrics(&encrypted), plain, "逐位实现无法解密查表实现的密文");
+
+    println!("明文 {} 字节, 密文 {} 字节", plain.len(), ciphertext.len());
+    bench("decrypt_lyrics", ciphertext.len(), || {
+        black_box(baseline::decrypt_lyrics(black_box(&encrypted)));
+    }, || {
+        black_box(decrypt_lyrics(black_box(&encrypted)).unwrap());
+    });
+    bench("encrypt_lyrics", plain.len(), || {
+        black_box(baseline::encrypt_lyrics(black_box(&plain)));
+    }, || {
+        black_box(encrypt_lyrics(black_box(&plain)).unwrap());
+    });
+}
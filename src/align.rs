@@ -0,0 +1,142 @@
+use crate::lyric::{Lyric, LyricLine, LyricMetadata};
+
+pub const DEFAULT_TOLERANCE_MS: u64 = 500;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AlignedLyric {
+    pub metadata: LyricMetadata,
+    pub lines: Vec<AlignedLine>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AlignedLine {
+    pub original: LyricLine,
+    pub translation: Option<LyricLine>,
+    pub romanization: Option<LyricLine>,
+}
+
+impl From<Lyric> for AlignedLyric {
+    fn from(lyric: Lyric) -> Self {
+        AlignedLyric {
+            metadata: lyric.metadata,
+            lines: lyric.lines.into_iter()
+                .map(|original| AlignedLine { original, translation: None, romanization: None })
+                .collect(),
+        }
+    }
+}
+
+impl AlignedLyric {
+    pub fn original(&self) -> Lyric {
+        Lyric {
+            metadata: self.metadata.clone(),
+            lines: self.lines.iter().map(|l| l.original.clone()).collect(),
+        }
+    }
+}
+
+pub fn align(original: &Lyric, translation: Option<&Lyric>, romanization: Option<&Lyric>, tolerance_ms: u64) -> AlignedLyric {
+    let mut aligned = AlignedLyric::from(original.clone());
+
+    // QRC 的行不保证按时间排序，按开始时间建立索引再查找，输出仍保持原文行序
+    let mut order: Vec<usize> = (0..aligned.lines.len()).collect();
+    order.sort_by_key(|&i| aligned.lines[i].original.start);
+    let starts: Vec<u64> = order.iter().map(|&i| aligned.lines[i].original.start).collect();
+
+    if let Some(translation) = translation {
+        for (index, line) in match_track(&starts, translation, tolerance_ms) {
+            aligned.lines[order[index]].translation = Some(line);
+        }
+    }
+    if let Some(romanization) = romanization {
+        for (index, line) in match_track(&starts, romanization, tolerance_ms) {
+            aligned.lines[order[index]].romanization = Some(line);
+        }
+    }
+
+    aligned
+}
+
+fn match_track(starts: &[u64], track: &Lyric, tolerance_ms: u64) -> Vec<(usize, LyricLine)> {
+    let mut best: Vec<Option<(u64, &LyricLine)>> = vec![None; starts.len()];
+
+    for line in track.lines.iter().filter(|l| !is_placeholder(l)) {
+        let Some(index) = nearest_index(starts, line.start) else { continue };
+        let distance = starts[index].abs_diff(line.start);
+        if distance > tolerance_ms {
+            continue;
+        }
+        if best[index].is_none_or(|(current, _)| distance < current) {
+            best[index] = Some((distance, line));
+        }
+    }
+
+    best.into_iter()
+        .enumerate()
+        .filter_map(|(index, entry)| entry.map(|(_, line)| (index, line.clone())))
+        .collect()
+}
+
+fn nearest_index(starts: &[u64], time: u64) -> Option<usize> {
+    if starts.is_empty() {
+        return None;
+    }
+    let pos = starts.partition_point(|&start| start < time);
+    let candidates = [pos.checked_sub(1), (pos < starts.len()).then_some(pos)];
+    candidates.into_iter()
+        .flatten()
+        .min_by_key(|&i| starts[i].abs_diff(time))
+}
+
+fn is_placeholder(line: &LyricLine) -> bool {
+    let text = line.text();
+    let text = text.trim();
+    text.is_empty() || text == "//"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lyric::Syllable;
+
+    fn lyric(lines: &[(u64, &str)]) -> Lyric {
+        Lyric {
+            lines: lines.iter().map(|&(start, text)| LyricLine {
+                start,
+                duration: 1000,
+                syllables: vec![Syllable { text: text.to_string(), start, duration: 1000 }],
+            }).collect(),
+            ..Lyric::default()
+        }
+    }
+
+    fn pairs(aligned: &AlignedLyric) -> Vec<(String, Option<String>)> {
+        aligned.lines.iter().map(|l| (l.original.text(), l.translation.as_ref().map(LyricLine::text))).collect()
+    }
+
+    #[test]
+    fn aligns_within_tolerance() {
+        let original = lyric(&[(1000, "a"), (3000, "b"), (5000, "c")]);
+        let translation = lyric(&[(1200, "甲"), (3000, "//"), (6000, "丙")]);
+        let aligned = align(&original, Some(&translation), None, DEFAULT_TOLERANCE_MS);
+
+        assert_eq!(pairs(&aligned), vec![
+            ("a".to_string(), Some("甲".to_string())),
+            ("b".to_string(), None),
+            ("c".to_string(), None),
+        ]);
+    }
+
+    #[test]
+    fn aligns_out_of_order_original() {
+        let original = lyric(&[(5000, "c"), (1000, "a"), (3000, "b")]);
+        let translation = lyric(&[(1000, "甲"), (3000, "乙"), (5000, "丙")]);
+        let aligned = align(&original, Some(&translation), None, DEFAULT_TOLERANCE_MS);
+
+        assert_eq!(pairs(&aligned), vec![
+            ("c".to_string(), Some("丙".to_string())),
+            ("a".to_string(), Some("甲".to_string())),
+            ("b".to_string(), Some("乙".to_string())),
+        ]);
+    }
+}
@@ -1,15 +1,22 @@
-use reqwest::Client; 
-use serde::{Deserialize, Serialize}; 
+use reqwest::{Client, header};
+use serde::{Deserialize, Serialize, de::DeserializeOwned}; 
 use std::collections::HashMap; 
-use std::time::{SystemTime, UNIX_EPOCH}; 
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use crate::{AppError, Result}; 
 use quick_xml::{Reader, events::Event};
+use once_cell::sync::Lazy;
+use regex::Regex;
 
 mod config {
-    pub const SEARCH_API_URL: &str = "https://u.y.qq.com/cgi-bin/musicu.fcg";
+    pub const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/135.0.0.0 Safari/537.36";
+    pub const QQ_MUSIC_REFERER: &str = "https://c.y.qq.com/";
+    pub const REQUEST_TIMEOUT_SECS: u64 = 30;
+
+    pub const MUSICU_API_URL: &str = "https://u.y.qq.com/cgi-bin/musicu.fcg";
     pub const LRC_API_URL: &str = "https://c.y.qq.com/lyric/fcgi-bin/fcg_query_lyric_new.fcg";
     pub const QRC_API_URL: &str = "https://c.y.qq.com/qqmusic/fcgi-bin/lyric_download.fcg";
     pub const SONG_DETAIL_API_URL: &str = "https://c.y.qq.com/v8/fcg-bin/fcg_play_single_song.fcg";
+    pub const ALBUM_COVER_URL_PREFIX: &str = "https://y.qq.com/music/photo_new/T002R300x300M000";
     
     pub struct CommonParams {
         pub g_tk: &'static str,
@@ -35,15 +42,15 @@ mod config {
 }
 
 #[derive(Debug, Serialize)]
-struct SearchRequest {
-    req_1: SearchRequestBody,
+struct MusicuRequest<P> {
+    req_1: MusicuRequestBody<P>,
 }
 
 #[derive(Debug, Serialize)]
-struct SearchRequestBody {
+struct MusicuRequestBody<P> {
     method: String,
     module: String,
-    param: SearchParam,
+    param: P,
 }
 
 #[derive(Debug, Serialize)]
@@ -57,42 +64,366 @@ struct SearchParam {
 #[derive(Debug, Deserialize, Clone)]
 pub struct MusicFcgApiResult {
     code: i32,
-    req_1: SearchResponse,
+    req_1: Option<MusicFcgModuleResult>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
-struct SearchResponse {
-    data: SearchData,
+struct MusicFcgModuleResult {
+    #[serde(default)]
+    code: i32,
+    #[serde(default)]
+    data: serde_json::Value,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 struct SearchData {
+    #[serde(default)]
+    meta: SearchMeta,
     body: SearchBody,
 }
 
+#[derive(Debug, Deserialize, Clone, Default)]
+struct SearchMeta {
+    #[serde(default)]
+    sum: u32,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 struct SearchBody {
-    song: SearchSong,
+    #[serde(default)]
+    song: SearchList<Song>,
+    #[serde(default)]
+    album: SearchList<Album>,
+    #[serde(default)]
+    singer: SearchList<SingerSummary>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
-struct SearchSong {
-    list: Vec<Song>,
+struct SearchList<T> {
+    list: Vec<T>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl<T> Default for SearchList<T> {
+    fn default() -> Self {
+        SearchList { list: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchType {
+    #[default]
+    Song,
+    Singer,
+    Album,
+    Lyric,
+}
+
+impl SearchType {
+    pub fn code(self) -> u32 {
+        match self {
+            SearchType::Song => 0,
+            SearchType::Singer => 1,
+            SearchType::Album => 2,
+            SearchType::Lyric => 7,
+        }
+    }
+}
+
+impl std::str::FromStr for SearchType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "song" => Ok(SearchType::Song),
+            "singer" => Ok(SearchType::Singer),
+            "album" => Ok(SearchType::Album),
+            "lyric" => Ok(SearchType::Lyric),
+            _ => Err(format!("未知的搜索类型: {}，可选 song、singer、album、lyric", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub keyword: String,
+    pub page: u32,
+    pub page_size: u32,
+    pub search_type: SearchType,
+}
+
+impl SearchQuery {
+    pub fn new(keyword: &str) -> Self {
+        SearchQuery { keyword: keyword.to_string(), page: 1, page_size: 20, search_type: SearchType::Song }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    pub total: u32,
+    pub page: u32,
+    pub page_size: u32,
+    pub songs: Vec<Song>,
+    pub albums: Vec<Album>,
+    pub singers: Vec<SingerSummary>,
+}
+
+impl SearchPage {
+    pub fn is_empty(&self) -> bool {
+        self.songs.is_empty() && self.albums.is_empty() && self.singers.is_empty()
+    }
+
+    pub fn page_count(&self) -> u32 {
+        self.total.div_ceil(self.page_size.max(1)).max(1)
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.page < self.page_count()
+    }
+
+    pub fn has_previous(&self) -> bool {
+        self.page > 1
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 pub struct Song {
     pub mid: String,
     pub name: String,
     pub singer: Vec<Singer>,
     pub id: u64,
+    #[serde(default)]
+    pub album: SongAlbum,
+    #[serde(default)]
+    pub interval: u32,
+    #[serde(default)]
+    pub subtitle: String,
+    #[serde(default)]
+    pub time_public: String,
+    #[serde(default)]
+    pub file: SongFile,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct SongAlbum {
+    #[serde(default)]
+    pub id: u64,
+    #[serde(default)]
+    pub mid: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Default)]
+pub struct SongFile {
+    #[serde(default)]
+    pub media_mid: String,
+    #[serde(default)]
+    pub size_128mp3: u64,
+    #[serde(default)]
+    pub size_320mp3: u64,
+    #[serde(default)]
+    pub size_flac: u64,
+}
+
+impl Song {
+    pub fn artists(&self) -> String {
+        self.singer.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join("/")
+    }
+
+    pub fn duration_text(&self) -> Option<String> {
+        (self.interval > 0).then(|| format!("{:02}:{:02}", self.interval / 60, self.interval % 60))
+    }
+
+    pub fn cover_url(&self) -> Option<String> {
+        (!self.album.mid.is_empty()).then(|| format!("{}{}.jpg", config::ALBUM_COVER_URL_PREFIX, self.album.mid))
+    }
+
+    pub fn file_formats(&self) -> Vec<&'static str> {
+        let sizes = [(self.file.size_128mp3, "128K MP3"), (self.file.size_320mp3, "320K MP3"), (self.file.size_flac, "FLAC")];
+        sizes.into_iter().filter(|(size, _)| *size > 0).map(|(_, label)| label).collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 pub struct Singer {
+    #[serde(default)]
+    pub mid: String,
+    #[serde(default)]
+    pub id: u64,
     pub name: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct SingerSummary {
+    #[serde(rename = "singerMID")]
+    pub mid: String,
+    #[serde(rename = "singerID", default)]
+    pub id: u64,
+    #[serde(rename = "singerName")]
+    pub name: String,
+    #[serde(rename = "songNum", default)]
+    pub song_count: u32,
+    #[serde(rename = "albumNum", default)]
+    pub album_count: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SingerSongPage {
+    pub singer_mid: String,
+    pub total: u32,
+    pub begin: u32,
+    pub songs: Vec<Song>,
+}
+
+#[derive(Debug, Serialize)]
+struct SingerSongListParam {
+    #[serde(rename = "singerMid")]
+    singer_mid: String,
+    begin: u32,
+    num: u32,
+    order: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct SingerSongListData {
+    #[serde(rename = "totalNum", default)]
+    total_num: u32,
+    #[serde(rename = "songList", default)]
+    song_list: Vec<SingerSongItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SingerSongItem {
+    #[serde(rename = "songInfo")]
+    song_info: Song,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Album {
+    #[serde(rename = "albumMID")]
+    pub mid: String,
+    #[serde(rename = "albumID", default)]
+    pub id: u64,
+    #[serde(rename = "albumName")]
+    pub name: String,
+    #[serde(rename = "singerName", default)]
+    pub singer_name: String,
+    #[serde(rename = "publicTime", default)]
+    pub public_time: String,
+    #[serde(default)]
+    pub song_count: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct AlbumTrack {
+    pub number: u32,
+    pub song: Song,
+}
+
+#[derive(Debug, Clone)]
+pub struct AlbumDetail {
+    pub mid: String,
+    pub name: String,
+    pub total: u32,
+    pub tracks: Vec<AlbumTrack>,
+}
+
+impl AlbumDetail {
+    pub fn singer_names(&self) -> String {
+        let mut names: Vec<&str> = Vec::new();
+        for singer in self.tracks.iter().flat_map(|t| &t.song.singer) {
+            if !names.contains(&singer.name.as_str()) {
+                names.push(&singer.name);
+            }
+        }
+        names.join("/")
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AlbumSongListParam {
+    #[serde(rename = "albumMid")]
+    album_mid: String,
+    begin: u32,
+    num: u32,
+    order: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlbumSongListData {
+    #[serde(rename = "totalNum", default)]
+    total_num: u32,
+    #[serde(rename = "songList", default)]
+    song_list: Vec<AlbumSongItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlbumSongItem {
+    #[serde(rename = "songInfo")]
+    song_info: AlbumSongInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlbumSongInfo {
+    #[serde(flatten)]
+    song: Song,
+    #[serde(default)]
+    index_album: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Playlist {
+    pub id: u64,
+    pub title: String,
+    pub creator: String,
+    pub total: u32,
+    pub songs: Vec<Song>,
+}
+
+#[derive(Debug, Serialize)]
+struct PlaylistParam {
+    disstid: u64,
+    userinfo: u32,
+    tag: u32,
+    orderlist: u32,
+    song_begin: u32,
+    song_num: u32,
+    onlysonglist: u32,
+    enc_host_uin: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaylistData {
+    #[serde(default)]
+    dirinfo: PlaylistInfo,
+    #[serde(default)]
+    songlist: Vec<Song>,
+    #[serde(default)]
+    total_song_num: u32,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct PlaylistInfo {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    host_nick: String,
+    #[serde(default)]
+    songnum: u32,
+}
+
+static PLAYLIST_ID_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:[?&](?:id|disstid)=|/playlist/)(\d+)").expect("未能编译歌单 ID 正则表达式")
+});
+
+pub fn parse_playlist_id(input: &str) -> Option<u64> {
+    let input = input.trim();
+    if let Ok(id) = input.parse::<u64>() {
+        return Some(id);
+    }
+    PLAYLIST_ID_REGEX.captures(input).and_then(|caps| caps[1].parse().ok())
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct LyricResult {
     pub retcode: i32,
@@ -107,6 +438,8 @@ pub struct QqLyricsResponse {
     pub lyrics: String,
     pub trans: String,
     pub roma: String,
+    // 翻译或罗马音解密失败时记录在这里，不影响主歌词
+    pub errors: Vec<AppError>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -114,23 +447,30 @@ struct SongApiResponse {
     data: Vec<Song>,
 }
 
-pub async fn search_song(client: &Client, keyword: &str) -> Result<(Vec<Song>, String)> {
-    let search_request = SearchRequest {
-        req_1: SearchRequestBody {
-            method: "DoSearchForQQMusicDesktop".to_string(),
-            module: "music.search.SearchCgiService".to_string(),
-            param: SearchParam {
-                num_per_page: 20,
-                page_num: 1,
-                query: keyword.to_string(),
-                search_type: 0,
-            },
+pub fn build_client() -> Result<Client> {
+    let mut headers = header::HeaderMap::new();
+    headers.insert(header::REFERER, header::HeaderValue::from_static(config::QQ_MUSIC_REFERER));
+    headers.insert(header::USER_AGENT, header::HeaderValue::from_static(config::USER_AGENT));
+
+    Client::builder()
+        .default_headers(headers)
+        .timeout(Duration::from_secs(config::REQUEST_TIMEOUT_SECS))
+        .build()
+        .map_err(AppError::Network)
+}
+
+async fn post_musicu<P: Serialize, T: DeserializeOwned>(client: &Client, module: &str, method: &str, param: P) -> Result<(Option<T>, String)> {
+    let request = MusicuRequest {
+        req_1: MusicuRequestBody {
+            method: method.to_string(),
+            module: module.to_string(),
+            param,
         },
     };
 
     let resp_text = client
-        .post(config::SEARCH_API_URL)
-        .json(&search_request)
+        .post(config::MUSICU_API_URL)
+        .json(&request)
         .send()
         .await?
         .text()
@@ -140,13 +480,163 @@ pub async fn search_song(client: &Client, keyword: &str) -> Result<(Vec<Song>, S
     
     let resp: MusicFcgApiResult = serde_json::from_str(&resp_text)?;
 
-    if resp.code == 0 {
-        Ok((resp.req_1.data.body.song.list, raw_response))
+    match resp.req_1 {
+        Some(module_result) if resp.code == 0 && module_result.code == 0 => {
+            Ok((Some(serde_json::from_value(module_result.data)?), raw_response))
+        },
+        _ => Ok((None, raw_response)),
+    }
+}
+
+pub async fn search(client: &Client, query: &SearchQuery) -> Result<(SearchPage, String)> {
+    let param = SearchParam {
+        num_per_page: query.page_size,
+        page_num: query.page,
+        query: query.keyword.clone(),
+        search_type: query.search_type.code(),
+    };
+    let (data, raw_response) = post_musicu::<_, SearchData>(client, "music.search.SearchCgiService", "DoSearchForQQMusicDesktop", param).await?;
+
+    let mut page = SearchPage {
+        total: 0,
+        page: query.page,
+        page_size: query.page_size,
+        songs: Vec::new(),
+        albums: Vec::new(),
+        singers: Vec::new(),
+    };
+    if let Some(data) = data {
+        page.total = data.meta.sum;
+        page.songs = data.body.song.list;
+        page.albums = data.body.album.list;
+        page.singers = data.body.singer.list;
+    }
+    Ok((page, raw_response))
+}
+
+pub async fn get_album_songs(client: &Client, album_mid: &str) -> Result<(Option<AlbumDetail>, String)> {
+    const PAGE_SIZE: u32 = 100;
+
+    let mut album = AlbumDetail { mid: album_mid.to_string(), name: String::new(), total: 0, tracks: Vec::new() };
+    let mut first_response = None;
+
+    loop {
+        let param = AlbumSongListParam { album_mid: album_mid.to_string(), begin: album.tracks.len() as u32, num: PAGE_SIZE, order: 2 };
+        let (data, raw_response) = post_musicu::<_, AlbumSongListData>(client, "music.musichallAlbum.AlbumSongList", "GetAlbumSongList", param).await?;
+        let first = first_response.get_or_insert(raw_response);
+        let Some(data) = data else { return Ok((None, first.clone())) };
+
+        album.total = data.total_num;
+        if data.song_list.is_empty() {
+            break;
+        }
+        for item in data.song_list {
+            let info = item.song_info;
+            if album.name.is_empty() {
+                album.name = info.song.album.name.clone();
+            }
+            let number = if info.index_album > 0 { info.index_album } else { album.tracks.len() as u32 + 1 };
+            album.tracks.push(AlbumTrack { number, song: info.song });
+        }
+        if album.tracks.len() as u32 >= album.total {
+            break;
+        }
+    }
+
+    let raw_response = first_response.unwrap_or_default();
+    if album.tracks.is_empty() {
+        Ok((None, raw_response))
+    } else {
+        Ok((Some(album), raw_response))
+    }
+}
+
+pub async fn get_singer_songs(client: &Client, singer_mid: &str, begin: u32, num: u32) -> Result<(Option<SingerSongPage>, String)> {
+    let param = SingerSongListParam { singer_mid: singer_mid.to_string(), begin, num, order: 1 };
+    let (data, raw_response) = post_musicu::<_, SingerSongListData>(client, "musichall.song_list_server", "GetSingerSongList", param).await?;
+
+    let page = data.map(|data| SingerSongPage {
+        singer_mid: singer_mid.to_string(),
+        total: data.total_num,
+        begin,
+        songs: data.song_list.into_iter().map(|item| item.song_info).collect(),
+    });
+    Ok((page.filter(|page| !page.songs.is_empty()), raw_response))
+}
+
+pub async fn get_all_singer_songs(client: &Client, singer_mid: &str) -> Result<(Option<Vec<Song>>, String)> {
+    const PAGE_SIZE: u32 = 100;
+
+    let mut songs: Vec<Song> = Vec::new();
+    let mut first_response = None;
+
+    loop {
+        let (page, raw_response) = get_singer_songs(client, singer_mid, songs.len() as u32, PAGE_SIZE).await?;
+        first_response.get_or_insert(raw_response);
+        let Some(page) = page else { break };
+
+        songs.extend(page.songs);
+        if songs.len() as u32 >= page.total {
+            break;
+        }
+    }
+
+    let raw_response = first_response.unwrap_or_default();
+    if songs.is_empty() {
+        Ok((None, raw_response))
+    } else {
+        Ok((Some(songs), raw_response))
+    }
+}
+
+pub async fn get_playlist(client: &Client, disstid: u64) -> Result<(Option<Playlist>, String)> {
+    const PAGE_SIZE: u32 = 100;
+
+    let mut playlist = Playlist { id: disstid, title: String::new(), creator: String::new(), total: 0, songs: Vec::new() };
+    let mut first_response = None;
+
+    loop {
+        let param = PlaylistParam {
+            disstid,
+            userinfo: 1,
+            tag: 1,
+            orderlist: 1,
+            song_begin: playlist.songs.len() as u32,
+            song_num: PAGE_SIZE,
+            onlysonglist: 0,
+            enc_host_uin: String::new(),
+        };
+        let (data, raw_response) = post_musicu::<_, PlaylistData>(client, "music.srfDissInfo.aiDissInfo", "uniform_get_Dissinfo", param).await?;
+        let first = first_response.get_or_insert(raw_response);
+        let Some(data) = data else { return Ok((None, first.clone())) };
+
+        if playlist.title.is_empty() {
+            playlist.title = data.dirinfo.title;
+            playlist.creator = data.dirinfo.host_nick;
+        }
+        playlist.total = data.total_song_num.max(data.dirinfo.songnum);
+        if data.songlist.is_empty() {
+            break;
+        }
+        playlist.songs.extend(data.songlist);
+        if playlist.songs.len() as u32 >= playlist.total {
+            break;
+        }
+    }
+
+    let raw_response = first_response.unwrap_or_default();
+    if playlist.songs.is_empty() {
+        Ok((None, raw_response))
     } else {
-        Ok((Vec::new(), raw_response))
+        Ok((Some(playlist), raw_response))
     }
 }
 
+pub async fn search_song(client: &Client, keyword: &str) -> Result<(Vec<Song>, String)> {
+    let (page, raw_response) = search(client, &SearchQuery::new(keyword)).await?;
+    Ok((page.songs, raw_response))
+}
+
 fn create_common_params(callback: &str) -> Vec<(&'static str, &str)> {
     let params = config::DEFAULT_PARAMS;
     vec![
@@ -202,10 +692,8 @@ pub async fn get_lyric(client: &Client, song_mid: &str) -> Result<(Option<LyricR
         result.lyric = crate::utils::decode_base64(&result.lyric)?;
     }
 
-    if let Some(trans) = result.trans.as_mut() {
-        if !trans.is_empty() {
-            *trans = crate::utils::decode_base64(trans)?;
-        }
+    if let Some(trans) = result.trans.as_mut() && !trans.is_empty() {
+        *trans = crate::utils::decode_base64(trans)?;
     }
     
     let has_content = !result.lyric.is_empty() || 
@@ -230,14 +718,17 @@ pub async fn get_lyrics_by_id(client: &Client, id: &str) -> Result<(Option<QqLyr
         .text()
         .await?;
 
-    let raw_response = resp_text.clone();
-    
+    Ok((parse_qrc_response(&resp_text)?, resp_text))
+}
+
+fn parse_qrc_response(resp_text: &str) -> Result<Option<QqLyricsResponse>> {
     let resp = resp_text.replace("<!--", "").replace("-->", "");
 
     let mut result = QqLyricsResponse {
         lyrics: String::new(),
         trans: String::new(),
         roma: String::new(),
+        errors: Vec::new(),
     };
 
     let mut reader = Reader::from_str(&resp);
@@ -247,31 +738,31 @@ pub async fn get_lyrics_by_id(client: &Client, id: &str) -> Result<(Option<QqLyr
     loop {
         match reader.read_event_into(&mut buf) {
             Ok(Event::Start(e)) => {
-                if let Ok(name) = std::str::from_utf8(e.name().as_ref()) {
-                    if name == "content" || name == "contentts" || name == "contentroma" {
-                        current_element = name.to_string();
-                    }
+                if let Ok(name) = std::str::from_utf8(e.name().as_ref())
+                    && (name == "content" || name == "contentts" || name == "contentroma") {
+                    current_element = name.to_string();
                 }
             },
             Ok(Event::CData(e)) if !current_element.is_empty() => {
-                if let Ok(cdata_text) = String::from_utf8(e.to_vec()) {
-                    if !cdata_text.is_empty() {
-                        if let Ok(decrypted) = crate::decrypto::decrypt_lyrics(&cdata_text) {
-                            match current_element.as_str() {
-                                "content" => result.lyrics = decrypted,
-                                "contentts" => result.trans = decrypted,
-                                "contentroma" => result.roma = decrypted,
-                                _ => {}
-                            }
-                        }
-                    }
+                let decrypted = String::from_utf8(e.to_vec())
+                    .map_err(AppError::from)
+                    .and_then(|cdata_text| match cdata_text.trim() {
+                        "" => Ok(String::new()),
+                        cdata_text => crate::decrypto::decrypt_lyrics(cdata_text),
+                    })
+                    .map_err(|source| AppError::LyricDecrypt { field: current_element.clone(), source: Box::new(source) });
+                match (current_element.as_str(), decrypted) {
+                    ("content", decrypted) => result.lyrics = decrypted?,
+                    ("contentts", Ok(decrypted)) => result.trans = decrypted,
+                    ("contentroma", Ok(decrypted)) => result.roma = decrypted,
+                    (_, Err(e)) => result.errors.push(e),
+                    _ => {}
                 }
             },
             Ok(Event::End(e)) => {
-                if let Ok(name) = std::str::from_utf8(e.name().as_ref()) {
-                    if name == "content" || name == "contentts" || name == "contentroma" {
-                        current_element.clear();
-                    }
+                if let Ok(name) = std::str::from_utf8(e.name().as_ref())
+                    && (name == "content" || name == "contentts" || name == "contentroma") {
+                    current_element.clear();
                 }
             },
             Ok(Event::Eof) => break,
@@ -282,9 +773,9 @@ pub async fn get_lyrics_by_id(client: &Client, id: &str) -> Result<(Option<QqLyr
     }
 
     if result.lyrics.is_empty() && result.trans.is_empty() && result.roma.is_empty() {
-        Ok((None, raw_response))
+        Ok(None)
     } else {
-        Ok((Some(result), raw_response))
+        Ok(Some(result))
     }
 }
 
@@ -323,3 +814,38 @@ pub async fn get_song(client: &Client, id_or_mid: &str) -> Result<(Option<Song>,
     let response: SongApiResponse = serde_json::from_str(&json_str)?;
     Ok((response.data.first().cloned(), json_str))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decrypto::encrypt_lyrics;
+
+    fn response(content: &str, trans: &str, roma: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"GB2312\" ?>\n<QrcInfos>\n<lyric>\n<content><![CDATA[{}]]></content>\n<contentts><![CDATA[{}]]></contentts>\n<contentroma><![CDATA[{}]]></contentroma>\n</lyric>\n</QrcInfos>",
+            content, trans, roma
+        )
+    }
+
+    #[test]
+    fn decrypts_all_fields() {
+        let lyrics = parse_qrc_response(&response(&encrypt_lyrics("主").unwrap(), &encrypt_lyrics("译").unwrap(), "")).unwrap().unwrap();
+
+        assert_eq!((lyrics.lyrics.as_str(), lyrics.trans.as_str(), lyrics.roma.as_str()), ("主", "译", ""));
+        assert!(lyrics.errors.is_empty());
+    }
+
+    #[test]
+    fn keeps_content_when_optional_track_fails() {
+        let lyrics = parse_qrc_response(&response(&encrypt_lyrics("主").unwrap(), "ABC", &encrypt_lyrics("roma").unwrap())).unwrap().unwrap();
+
+        assert_eq!((lyrics.lyrics.as_str(), lyrics.trans.as_str(), lyrics.roma.as_str()), ("主", "", "roma"));
+        assert!(matches!(lyrics.errors.as_slice(), [AppError::LyricDecrypt { field, .. }] if field == "contentts"));
+    }
+
+    #[test]
+    fn fails_when_content_fails() {
+        let result = parse_qrc_response(&response("ABC", &encrypt_lyrics("译").unwrap(), ""));
+        assert!(matches!(result, Err(AppError::LyricDecrypt { field, .. }) if field == "content"));
+    }
+}
@@ -1,128 +1,181 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
 use std::path::Path;
-use std::num::ParseIntError;
-use once_cell::sync::Lazy;
-use regex::Regex;
-
-use crate::AppError;
-
-const MILLISECONDS_PER_SECOND: usize = 1000;
-const MILLISECONDS_PER_MINUTE: usize = 60 * MILLISECONDS_PER_SECOND;
-const MILLISECONDS_PER_HOUR: usize = 60 * MILLISECONDS_PER_MINUTE;
-const CENTISECONDS_TO_MILLISECONDS: usize = 10; 
-const K_TAG_MULTIPLIER: usize = 10; 
-const QRC_GAP_THRESHOLD_MS: usize = 200;
-
-static QRC_TIMESTAMP_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"\[(\d+),(\d+)\]").expect("未能编译行时间正则表达式")
-});
-static WORD_TIME_TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"\((\d+),(\d+)\)").expect("未能编译词时间正则表达式")
-});
+
+use crate::align::AlignedLyric;
+use crate::ass_style::{AssLayout, AssProfile, KaraokeOptions};
+use crate::lyric::{Lyric, LyricLine, LyricMetadata, Syllable};
+use crate::{AppError, qrc};
+
+const MILLISECONDS_PER_SECOND: u64 = 1000;
+const MILLISECONDS_PER_MINUTE: u64 = 60 * MILLISECONDS_PER_SECOND;
+const MILLISECONDS_PER_HOUR: u64 = 60 * MILLISECONDS_PER_MINUTE;
+const CENTISECONDS_TO_MILLISECONDS: u64 = 10; 
+const K_TAG_MULTIPLIER: u64 = 10; 
+const QRC_GAP_THRESHOLD_MS: u64 = 200;
 
 pub fn convert_qrc_to_ass(qrc_path: &Path, ass_path: &Path) -> Result<(), AppError> {
-    let file = File::open(qrc_path)?;
-    let reader = BufReader::new(file);
+    convert_qrc_to_ass_with(qrc_path, ass_path, &AssProfile::default())
+}
+
+pub fn convert_qrc_to_ass_with(qrc_path: &Path, ass_path: &Path, profile: &AssProfile) -> Result<(), AppError> {
+    let lyric = qrc::parse(&fs::read_to_string(qrc_path)?)?;
+    write_aligned_ass_file(&AlignedLyric::from(lyric), ass_path, profile)
+}
+
+pub fn convert_qrc_str_to_ass(qrc_content: &str, writer: &mut impl Write, profile: &AssProfile) -> Result<(), AppError> {
+    write_ass(writer, &AlignedLyric::from(qrc::parse(qrc_content)?), profile)
+}
+
+pub fn write_aligned_ass_file(aligned: &AlignedLyric, ass_path: &Path, profile: &AssProfile) -> Result<(), AppError> {
     let mut writer = BufWriter::new(File::create(ass_path)?);
+    write_ass(&mut writer, aligned, profile)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_ass(writer: &mut impl Write, aligned: &AlignedLyric, profile: &AssProfile) -> Result<(), AppError> {
+    write_ass_header(writer, &aligned.metadata, profile)?;
+    write_ass_events(writer, aligned, profile)
+}
 
-    write_ass_header(&mut writer)?;
+struct Placement {
+    start: u64,
+    end: u64,
+    position: String,
+}
 
-    for line_result in reader.lines() {
-        let line = line_result?;
+fn place_lines(aligned: &AlignedLyric, profile: &AssProfile) -> Vec<Placement> {
+    let pre_display_ms = profile.karaoke.pre_display_ms;
+    let mut placements: Vec<Placement> = aligned.lines.iter()
+        .map(|line| Placement { start: line.original.start.saturating_sub(pre_display_ms), end: line.original.end(), position: String::new() })
+        .collect();
 
-        if !line.starts_with('[') {
-            continue;
+    if profile.layout != AssLayout::TwoRow {
+        return placements;
+    }
+
+    let style = &profile.original;
+    let bottom_y = profile.play_res_y.saturating_sub(style.margin_v);
+    let top_y = bottom_y.saturating_sub(style.font_size * 6 / 5);
+    let right_x = profile.play_res_x.saturating_sub(style.margin_r);
+
+    for i in 0..placements.len() {
+        let start = aligned.lines[i].original.start;
+        let mut display_start = match i {
+            0 => placements[0].start,
+            _ => placements[i].start.min(aligned.lines[i - 1].original.start),
+        };
+        if i >= 2 {
+            let same_row = &mut placements[i - 2];
+            same_row.end = same_row.end.min(start);
+            display_start = display_start.max(same_row.end);
         }
 
-        if let Some(ts_caps) = QRC_TIMESTAMP_REGEX.captures(&line) {
-            let header_start_ms: usize = ts_caps[1].parse().map_err(|e: ParseIntError| AppError::InvalidHex(e))?;
-            let header_duration_ms: usize = ts_caps[2].parse().map_err(|e: ParseIntError| AppError::InvalidHex(e))?;
-            let header_end_ms = header_start_ms + header_duration_ms;
-
-            let start_time_ass = milliseconds_to_time(header_start_ms);
-            let end_time_ass = milliseconds_to_time(header_end_ms);
-
-            let mut ass_text = String::new();
-            let mut last_word_end_ms = header_start_ms;
-
-            let content_part = &line[ts_caps.get(0).unwrap().end()..];
-                        
-            let mut time_positions = Vec::new();
-            for cap in WORD_TIME_TAG_REGEX.captures_iter(content_part) {
-                let start_pos = cap.get(0).unwrap().start();
-                let end_pos = cap.get(0).unwrap().end();
-                let start_ms: usize = cap[1].parse().map_err(|e: ParseIntError| AppError::InvalidHex(e))?;
-                let duration_ms: usize = cap[2].parse().map_err(|e: ParseIntError| AppError::InvalidHex(e))?;
-                
-                time_positions.push((start_pos, end_pos, start_ms, duration_ms));
-            }
-            
-            time_positions.sort_by_key(|&(pos, _, _, _)| pos);
-            
-            for i in 0..time_positions.len() {
-                let (start_pos, _end_pos, current_word_start_ms, current_word_duration_ms) = time_positions[i];
-                
-                let word_start = if i == 0 {
-                    0
-                } else {
-                    time_positions[i-1].1
-                };
-                
-                let word = &content_part[word_start..start_pos];
-                
-                if current_word_start_ms > last_word_end_ms {
-                    let gap_ms = current_word_start_ms - last_word_end_ms;
-                    let gap_k_value = (gap_ms + K_TAG_MULTIPLIER / 2) / K_TAG_MULTIPLIER;
-                    if gap_k_value > 0 {
-                        ass_text.push_str(&format!("{{\\k{}}}", gap_k_value));
-                    }
-                }
-                
-                let word_k_value = (current_word_duration_ms + K_TAG_MULTIPLIER / 2) / K_TAG_MULTIPLIER;
-                if word_k_value > 0 && !word.is_empty() {
-                    ass_text.push_str(&format!("{{\\k{}}}{}", word_k_value, word));
-                } else if !word.is_empty() {
-                    ass_text.push_str(word);
-                }
-                
-                last_word_end_ms = current_word_start_ms + current_word_duration_ms;
-            }
+        let placement = &mut placements[i];
+        placement.start = display_start.min(start);
+        placement.position = if i % 2 == 0 {
+            format!("{{\\an1\\pos({},{})}}", style.margin_l, top_y)
+        } else {
+            format!("{{\\an3\\pos({},{})}}", right_x, bottom_y)
+        };
+    }
 
-            if last_word_end_ms < header_end_ms && (header_end_ms - last_word_end_ms) > QRC_GAP_THRESHOLD_MS {
-                let final_gap_ms = header_end_ms - last_word_end_ms;
-                let final_gap_k_value = (final_gap_ms + K_TAG_MULTIPLIER / 2) / K_TAG_MULTIPLIER;
-                if final_gap_k_value > 0 {
-                    ass_text.push_str(&format!("{{\\k{}}}", final_gap_k_value));
-                }
-            }
+    placements
+}
 
-            let ass_text = ass_text.replace("{\\k0}", "");
-            if !ass_text.is_empty() {
-                writeln!(
-                    writer,
-                    "Dialogue: 0,{},{},Default,,0,0,0,,{}", 
-                    start_time_ass,
-                    end_time_ass,
-                    ass_text 
-                )?;
-            }
+fn write_ass_events(writer: &mut impl Write, aligned: &AlignedLyric, profile: &AssProfile) -> Result<(), AppError> {
+    let karaoke = &profile.karaoke;
+    let tag = karaoke.effect.tag();
+
+    for (line, placement) in aligned.lines.iter().zip(place_lines(aligned, profile)) {
+        let ass_text = karaoke_text(&line.original, placement.start, tag);
+        if !ass_text.is_empty() {
+            write_dialogue(writer, 0, "Default", placement.start, placement.end, &format!("{}{}", placement.position, ass_text), karaoke)?;
+        }
+        if let Some(translation) = &line.translation {
+            write_dialogue(writer, 1, "Translation", placement.start, placement.end, &translation.text(), karaoke)?;
+        }
+        if let Some(romanization) = &line.romanization {
+            let start = romanization.start.saturating_sub(karaoke.pre_display_ms);
+            write_dialogue(writer, 2, "Romanization", start, romanization.end(), &karaoke_text(romanization, start, tag), karaoke)?;
         }
     }
+    Ok(())
+}
 
-    writer.flush()?;
+fn write_dialogue(writer: &mut impl Write, layer: u32, style: &str, start_ms: u64, end_ms: u64, ass_text: &str, karaoke: &KaraokeOptions) -> Result<(), AppError> {
+    if !ass_text.is_empty() {
+        let fade = if karaoke.fade_in_ms > 0 || karaoke.fade_out_ms > 0 {
+            format!("{{\\fad({},{})}}", karaoke.fade_in_ms, karaoke.fade_out_ms)
+        } else {
+            String::new()
+        };
+        writeln!(
+            writer,
+            "Dialogue: {},{},{},{},,0,0,0,,{}{}", 
+            layer,
+            milliseconds_to_time(start_ms),
+            milliseconds_to_time(end_ms),
+            style,
+            fade,
+            ass_text 
+        )?;
+    }
     Ok(())
 }
 
-fn write_ass_header(writer: &mut BufWriter<File>) -> Result<(), AppError> {
+fn karaoke_text(line: &LyricLine, display_start_ms: u64, tag: &str) -> String {
+    let mut ass_text = String::new();
+    let mut last_word_end_ms = display_start_ms;
+
+    for syllable in &line.syllables {
+        if syllable.start > last_word_end_ms {
+            let gap_k_value = k_value(syllable.start - last_word_end_ms);
+            if gap_k_value > 0 {
+                ass_text.push_str(&format!("{{\\k{}}}", gap_k_value));
+            }
+        }
+
+        let word_k_value = k_value(syllable.duration);
+        if word_k_value > 0 && !syllable.text.is_empty() {
+            ass_text.push_str(&format!("{{\\{}{}}}{}", tag, word_k_value, syllable.text));
+        } else if !syllable.text.is_empty() {
+            ass_text.push_str(&syllable.text);
+        }
+
+        last_word_end_ms = syllable.end();
+    }
+
+    let header_end_ms = line.end();
+    if last_word_end_ms < header_end_ms && (header_end_ms - last_word_end_ms) > QRC_GAP_THRESHOLD_MS {
+        let final_gap_k_value = k_value(header_end_ms - last_word_end_ms);
+        if final_gap_k_value > 0 {
+            ass_text.push_str(&format!("{{\\k{}}}", final_gap_k_value));
+        }
+    }
+
+    ass_text.replace("{\\k0}", "")
+}
+
+fn k_value(ms: u64) -> u64 {
+    ms.saturating_add(K_TAG_MULTIPLIER / 2) / K_TAG_MULTIPLIER
+}
+
+fn write_ass_header(writer: &mut impl Write, metadata: &LyricMetadata, profile: &AssProfile) -> Result<(), AppError> {
     writeln!(writer, "[Script Info]")?;
-    writeln!(writer, "PlayResX: 1920")?;
-    writeln!(writer, "PlayResY: 1440")?;
+    for (key, value) in script_info_fields(metadata) {
+        writeln!(writer, "{}: {}", key, value)?;
+    }
+    writeln!(writer, "PlayResX: {}", profile.play_res_x)?;
+    writeln!(writer, "PlayResY: {}", profile.play_res_y)?;
     writeln!(writer)?;
     
     writeln!(writer, "[V4+ Styles]")?;
     writeln!(writer, "Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding")?;
-    writeln!(writer, "Style: Default,微软雅黑,100,&H00FFFFFF,&H004E503F,&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,1,0,2,10,10,10,1")?;
+    writeln!(writer, "{}", profile.original.to_style_line("Default"))?;
+    writeln!(writer, "{}", profile.translation.to_style_line("Translation"))?;
+    writeln!(writer, "{}", profile.romanization.to_style_line("Romanization"))?;
     writeln!(writer)?;
 
     writeln!(writer, "[Events]")?;
@@ -131,7 +184,20 @@ fn write_ass_header(writer: &mut BufWriter<File>) -> Result<(), AppError> {
     Ok(())
 }
 
-fn milliseconds_to_time(ms: usize) -> String {
+const SCRIPT_INFO_TAGS: [(&str, &str); 4] = [("Title", "ti"), ("Artist", "ar"), ("Album", "al"), ("Length", "length")];
+
+fn script_info_fields(metadata: &LyricMetadata) -> Vec<(&'static str, String)> {
+    let tags = metadata.tags();
+    SCRIPT_INFO_TAGS.iter()
+        .filter_map(|(field, tag)| {
+            tags.iter()
+                .find(|(key, value)| key == tag && !value.is_empty())
+                .map(|(_, value)| (*field, value.clone()))
+        })
+        .collect()
+}
+
+fn milliseconds_to_time(ms: u64) -> String {
     let hours = ms / MILLISECONDS_PER_HOUR;
     let remaining = ms % MILLISECONDS_PER_HOUR;
     let minutes = remaining / MILLISECONDS_PER_MINUTE;
@@ -140,4 +206,258 @@ fn milliseconds_to_time(ms: usize) -> String {
     let centiseconds = (remaining % MILLISECONDS_PER_SECOND) / CENTISECONDS_TO_MILLISECONDS;
 
     format!("{:01}:{:02}:{:02}.{:02}", hours, minutes, seconds, centiseconds)
-}
\ No newline at end of file
+}
+
+pub fn convert_ass_to_qrc(ass_path: &Path, qrc_path: &Path) -> Result<(), AppError> {
+    let lyric = parse_ass(&fs::read_to_string(ass_path)?)?;
+    fs::write(qrc_path, qrc::write(&lyric))?;
+    Ok(())
+}
+
+pub fn parse_ass(content: &str) -> Result<Lyric, AppError> {
+    let mut lyric = Lyric::default();
+    let mut section = String::new();
+    let mut format: Vec<String> = Vec::new();
+    let mut lines = Vec::new();
+
+    for (index, raw_line) in content.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line = raw_line.trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line.to_ascii_lowercase();
+            continue;
+        }
+
+        match section.as_str() {
+            "[script info]" => {
+                if let Some((key, value)) = line.split_once(':')
+                    && let Some((_, tag)) = SCRIPT_INFO_TAGS.iter().find(|(field, _)| *field == key.trim()) {
+                    lyric.metadata.set_tag(tag, value);
+                }
+            },
+            "[events]" => {
+                if let Some(fields) = line.strip_prefix("Format:") {
+                    format = fields.split(',').map(|f| f.trim().to_ascii_lowercase()).collect();
+                } else if let Some(fields) = line.strip_prefix("Dialogue:") {
+                    lines.push(parse_dialogue(fields, &format, index + 1)?);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    // 翻译和罗马音也可能带卡拉 OK 标签，只保留主歌词所用样式的行
+    let main = lines.iter().find(|d| d.karaoke && d.style.eq_ignore_ascii_case("Default"))
+        .or_else(|| lines.iter().find(|d| d.karaoke))
+        .or_else(|| lines.iter().find(|d| d.style.eq_ignore_ascii_case("Default")))
+        .or_else(|| lines.first())
+        .map(|d| (d.style.clone(), d.karaoke));
+    if let Some((style, karaoke)) = main {
+        lyric.lines = lines.into_iter()
+            .filter(|d| d.karaoke == karaoke && d.style.eq_ignore_ascii_case(&style))
+            .map(|d| d.line)
+            .collect();
+    }
+    lyric.lines.sort_by_key(|line| line.start);
+    Ok(lyric)
+}
+
+struct Dialogue {
+    line: LyricLine,
+    style: String,
+    karaoke: bool,
+}
+
+fn parse_dialogue(fields: &str, format: &[String], line_no: usize) -> Result<Dialogue, AppError> {
+    let error = |message: String| AppError::LyricParse { line: line_no, column: 1, message };
+    if format.is_empty() {
+        return Err(error("Dialogue 行之前缺少 Format 行".to_string()));
+    }
+
+    let values: Vec<&str> = fields.trim_start().splitn(format.len(), ',').collect();
+    let field = |name: &str| format.iter().position(|f| f == name).and_then(|i| values.get(i)).map(|v| v.trim());
+    let start_field = field("start").ok_or_else(|| error("缺少 Start 字段".to_string()))?;
+    let end_field = field("end").ok_or_else(|| error("缺少 End 字段".to_string()))?;
+    let style = field("style").unwrap_or("").to_string();
+    let text = format.iter().position(|f| f == "text").and_then(|i| values.get(i)).copied().unwrap_or("");
+
+    let start = time_to_milliseconds(start_field).ok_or_else(|| error(format!("无效的开始时间 '{}'", start_field)))?;
+    let end = time_to_milliseconds(end_field).ok_or_else(|| error(format!("无效的结束时间 '{}'", end_field)))?;
+
+    let mut syllables: Vec<Syllable> = Vec::new();
+    let mut cursor = start;
+    let mut karaoke = false;
+    let mut current: Option<Syllable> = None;
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        if let Some(block_start) = rest.strip_prefix('{') {
+            let close = block_start.find('}').unwrap_or(block_start.len());
+            for k_value in karaoke_values(&block_start[..close]) {
+                karaoke = true;
+                if let Some(syllable) = current.take().filter(|s| !s.text.is_empty()) {
+                    syllables.push(syllable);
+                }
+                let out_of_range = || error(format!("卡拉 OK 时长超出范围 '\\k{}'", k_value));
+                let duration = k_value.checked_mul(K_TAG_MULTIPLIER).ok_or_else(out_of_range)?;
+                current = Some(Syllable { text: String::new(), start: cursor, duration });
+                cursor = cursor.checked_add(duration).ok_or_else(out_of_range)?;
+            }
+            rest = block_start.get(close + 1..).unwrap_or("");
+            continue;
+        }
+
+        let next = rest.find('{').unwrap_or(rest.len());
+        let chunk = rest[..next].replace("\\N", " ").replace("\\n", " ").replace("\\h", " ");
+        match current.as_mut() {
+            Some(syllable) => syllable.text.push_str(&chunk),
+            None => plain.push_str(&chunk),
+        }
+        rest = &rest[next..];
+    }
+    if let Some(syllable) = current.filter(|s| !s.text.is_empty()) {
+        syllables.push(syllable);
+    }
+
+    if !karaoke {
+        let duration = end.saturating_sub(start);
+        let line = LyricLine { start, duration, syllables: vec![Syllable { text: plain, start, duration }] };
+        return Ok(Dialogue { line, style, karaoke: false });
+    }
+
+    if !plain.is_empty() {
+        syllables.insert(0, Syllable { text: plain, start, duration: 0 });
+    }
+    let line_start = syllables.first().map_or(start, |s| s.start);
+    let line = LyricLine { start: line_start, duration: end.saturating_sub(line_start), syllables };
+    Ok(Dialogue { line, style, karaoke: true })
+}
+
+fn karaoke_values(block: &str) -> Vec<u64> {
+    block.split('\\')
+        .skip(1)
+        .filter_map(|tag| {
+            let digits = tag.strip_prefix("kf").or_else(|| tag.strip_prefix("ko"))
+                .or_else(|| tag.strip_prefix('k')).or_else(|| tag.strip_prefix('K'))?;
+            digits.trim().parse::<u64>().ok()
+        })
+        .collect()
+}
+
+fn time_to_milliseconds(time: &str) -> Option<u64> {
+    let mut parts = time.split(':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let (seconds, fraction) = parts.next()?.split_once('.')?;
+    if parts.next().is_some() || fraction.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let seconds: u64 = seconds.parse().ok()?;
+    // 小数部分按位数换算，"1.5" 是 500 毫秒而不是 50 毫秒
+    let millis = match fraction.len() {
+        1 => fraction.parse::<u64>().ok()? * 100,
+        2 => fraction.parse::<u64>().ok()? * CENTISECONDS_TO_MILLISECONDS,
+        3 => fraction.parse::<u64>().ok()?,
+        _ => return None,
+    };
+    hours.checked_mul(MILLISECONDS_PER_HOUR)?
+        .checked_add(minutes.checked_mul(MILLISECONDS_PER_MINUTE)?)?
+        .checked_add(seconds.checked_mul(MILLISECONDS_PER_SECOND)?)?
+        .checked_add(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align::align;
+
+    fn render(aligned: &AlignedLyric) -> String {
+        let mut output = Vec::new();
+        write_ass(&mut output, aligned, &AssProfile::default()).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn texts(lyric: &Lyric) -> Vec<String> {
+        lyric.lines.iter().map(LyricLine::text).collect()
+    }
+
+    #[test]
+    fn round_trips_karaoke_lines() {
+        let original = qrc::parse("[ti:晴天]\n[1000,1000]歌(1000,500)词(1500,500)\n[3000,1000]下(3000,500)一(3500,500)\n").unwrap();
+        let lyric = parse_ass(&render(&AlignedLyric::from(original.clone()))).unwrap();
+
+        assert_eq!(lyric.metadata.title.as_deref(), Some("晴天"));
+        assert_eq!(lyric.lines, original.lines);
+    }
+
+    #[test]
+    fn ignores_translation_and_romanization_layers() {
+        let original = qrc::parse("[1000,1000]歌(1000,500)词(1500,500)\n[3000,1000]下(3000,500)一(3500,500)\n").unwrap();
+        let translation = crate::lrc::parse("[00:01.00]lyric\n[00:03.00]next\n").unwrap();
+        let romanization = qrc::parse("[1000,1000]ge (1000,500)ci(1500,500)\n[3000,1000]xia (3000,500)yi(3500,500)\n").unwrap();
+        let aligned = align(&original, Some(&translation), Some(&romanization), 500);
+
+        let lyric = parse_ass(&render(&aligned)).unwrap();
+        assert_eq!(texts(&lyric), vec!["歌词", "下一"]);
+    }
+
+    #[test]
+    fn falls_back_to_first_karaoke_style() {
+        let content = "[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+            Dialogue: 0,0:00:00.00,0:00:05.00,Default,,0,0,0,,标题\n\
+            Dialogue: 0,0:00:01.00,0:00:02.00,Karaoke,,0,0,0,,{\\k50}a{\\k50}b\n\
+            Dialogue: 1,0:00:01.00,0:00:02.00,Sub,,0,0,0,,{\\k100}x\n";
+        let lyric = parse_ass(content).unwrap();
+
+        assert_eq!(texts(&lyric), vec!["ab"]);
+        assert_eq!(lyric.lines[0].syllables[1], Syllable { text: "b".to_string(), start: 1500, duration: 500 });
+    }
+
+    #[test]
+    fn places_two_row_lines_without_overlap() {
+        let original = qrc::parse("[1000,3000]a(1000,3000)\n[2000,1000]b(2000,1000)\n[3500,1000]c(3500,1000)\n[5000,1000]d(5000,1000)\n").unwrap();
+        let profile = AssProfile {
+            layout: AssLayout::TwoRow,
+            karaoke: KaraokeOptions { pre_display_ms: 1000, ..KaraokeOptions::default() },
+            ..AssProfile::default()
+        };
+        let placements = place_lines(&AlignedLyric::from(original), &profile);
+
+        let top = "{\\an1\\pos(10,1310)}";
+        let bottom = "{\\an3\\pos(1910,1430)}";
+        let actual: Vec<(u64, u64, &str)> = placements.iter().map(|p| (p.start, p.end, p.position.as_str())).collect();
+        assert_eq!(actual, vec![
+            // 第一行提前显示，并在同一行的第三句开始时结束
+            (0, 3500, top),
+            (1000, 3000, bottom),
+            (3500, 4500, top),
+            // 上一句开始时就提前显示
+            (3500, 6000, bottom),
+        ]);
+        for pair in placements.windows(3) {
+            assert!(pair[0].end <= pair[2].start);
+        }
+    }
+
+
+    #[test]
+    fn scales_fractions_by_digit_count() {
+        assert_eq!(time_to_milliseconds("0:00:01.5"), Some(1500));
+        assert_eq!(time_to_milliseconds("0:00:01.50"), Some(1500));
+        assert_eq!(time_to_milliseconds("0:00:01.005"), Some(1005));
+        assert_eq!(time_to_milliseconds("1:02:03.04"), Some(3_723_040));
+        assert_eq!(time_to_milliseconds("0:00:01.0005"), None);
+        assert_eq!(time_to_milliseconds("99999999999999999:00:00.00"), None);
+    }
+
+    #[test]
+    fn rejects_out_of_range_karaoke_durations() {
+        let content = "[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+            Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,{\\k50}a{\\k18446744073709551615}b\n";
+        match parse_ass(content) {
+            Err(AppError::LyricParse { line, .. }) => assert_eq!(line, 3),
+            other => panic!("应返回解析错误: {:?}", other),
+        }
+    }
+
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{AppError, Result};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssStyle {
+    pub font_name: String,
+    pub font_size: u32,
+    pub primary_colour: String,
+    pub secondary_colour: String,
+    pub outline_colour: String,
+    pub back_colour: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub outline: u32,
+    pub shadow: u32,
+    pub alignment: u8,
+    pub margin_l: u32,
+    pub margin_r: u32,
+    pub margin_v: u32,
+}
+
+impl Default for AssStyle {
+    fn default() -> Self {
+        AssStyle {
+            font_name: "微软雅黑".to_string(),
+            font_size: 100,
+            primary_colour: "&H00FFFFFF".to_string(),
+            secondary_colour: "&H004E503F".to_string(),
+            outline_colour: "&H00000000".to_string(),
+            back_colour: "&H00000000".to_string(),
+            bold: false,
+            italic: false,
+            outline: 1,
+            shadow: 0,
+            alignment: 2,
+            margin_l: 10,
+            margin_r: 10,
+            margin_v: 10,
+        }
+    }
+}
+
+impl AssStyle {
+    pub fn to_style_line(&self, name: &str) -> String {
+        let flag = |on: bool| if on { -1 } else { 0 };
+        format!(
+            "Style: {},{},{},{},{},{},{},{},{},0,0,100,100,0,0,1,{},{},{},{},{},{},1",
+            name, self.font_name, self.font_size,
+            self.primary_colour, self.secondary_colour, self.outline_colour, self.back_colour,
+            flag(self.bold), flag(self.italic),
+            self.outline, self.shadow, self.alignment,
+            self.margin_l, self.margin_r, self.margin_v
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KaraokeEffect {
+    #[default]
+    K,
+    Kf,
+    Ko,
+}
+
+impl KaraokeEffect {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            KaraokeEffect::K => "k",
+            KaraokeEffect::Kf => "kf",
+            KaraokeEffect::Ko => "ko",
+        }
+    }
+}
+
+impl FromStr for KaraokeEffect {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().trim_start_matches('\\') {
+            "k" => Ok(KaraokeEffect::K),
+            "kf" | "sweep" => Ok(KaraokeEffect::Kf),
+            "ko" | "outline" => Ok(KaraokeEffect::Ko),
+            _ => Err(AppError::InvalidInput),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssLayout {
+    #[default]
+    Single,
+    TwoRow,
+}
+
+impl FromStr for AssLayout {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "single" => Ok(AssLayout::Single),
+            "two-row" | "ktv" => Ok(AssLayout::TwoRow),
+            _ => Err(AppError::InvalidInput),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KaraokeOptions {
+    pub effect: KaraokeEffect,
+    pub fade_in_ms: u64,
+    pub fade_out_ms: u64,
+    pub pre_display_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssProfile {
+    pub play_res_x: u32,
+    pub play_res_y: u32,
+    pub original: AssStyle,
+    pub translation: AssStyle,
+    pub romanization: AssStyle,
+    pub karaoke: KaraokeOptions,
+    pub layout: AssLayout,
+}
+
+impl Default for AssProfile {
+    fn default() -> Self {
+        AssProfile {
+            play_res_x: 1920,
+            play_res_y: 1440,
+            original: AssStyle::default(),
+            translation: AssStyle { font_size: 70, margin_v: 220, ..AssStyle::default() },
+            romanization: AssStyle { font_size: 60, margin_v: 130, ..AssStyle::default() },
+            karaoke: KaraokeOptions::default(),
+            layout: AssLayout::Single,
+        }
+    }
+}
+
+impl AssProfile {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn set_font_name(&mut self, font_name: &str) {
+        for style in [&mut self.original, &mut self.translation, &mut self.romanization] {
+            style.font_name = font_name.to_string();
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use reqwest::Client;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::api::{self, AlbumDetail, Playlist, Song};
+use crate::download::{LyricFormat, SaveOptions, download_lyrics_as};
+use crate::{AppError, Result, utils};
+
+pub const DEFAULT_PARALLELISM: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchEntry {
+    Id(u64),
+    Mid(String),
+    Query { artist: String, title: String },
+    Track { number: u32, width: usize, song: Song },
+    Song(Song),
+}
+
+impl BatchEntry {
+    pub fn parse(line: &str) -> Option<BatchEntry> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        if let Some((artist, title)) = line.split_once(" - ") {
+            return Some(BatchEntry::Query { artist: artist.trim().to_string(), title: title.trim().to_string() });
+        }
+
+        match line.parse::<u64>() {
+            Ok(id) => Some(BatchEntry::Id(id)),
+            Err(_) => Some(BatchEntry::Mid(line.to_string())),
+        }
+    }
+
+    fn file_name(&self, song: &Song) -> String {
+        match self {
+            BatchEntry::Track { number, width, .. } => format!("{:0width$}. {}", number, utils::create_safe_filename(song), width = width),
+            _ => utils::create_safe_filename(song),
+        }
+    }
+}
+
+impl fmt::Display for BatchEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchEntry::Id(id) => write!(f, "ID {}", id),
+            BatchEntry::Mid(mid) => write!(f, "MID {}", mid),
+            BatchEntry::Query { artist, title } => write!(f, "{} - {}", artist, title),
+            BatchEntry::Track { number, width, song } => write!(f, "{:0width$}. {}", number, song.name, width = width),
+            BatchEntry::Song(song) => write!(f, "{} - {}", song.artists(), song.name),
+        }
+    }
+}
+
+pub fn parse_batch_list(content: &str) -> Vec<BatchEntry> {
+    content.lines().filter_map(BatchEntry::parse).collect()
+}
+
+#[derive(Debug)]
+pub enum BatchOutcome {
+    Succeeded(Vec<PathBuf>),
+    NotFound,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BatchProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub results: Vec<(BatchEntry, BatchOutcome)>,
+}
+
+impl BatchReport {
+    pub fn succeeded(&self) -> usize {
+        self.results.iter().filter(|(_, o)| matches!(o, BatchOutcome::Succeeded(_))).count()
+    }
+
+    pub fn not_found(&self) -> usize {
+        self.results.iter().filter(|(_, o)| matches!(o, BatchOutcome::NotFound)).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|(_, o)| matches!(o, BatchOutcome::Failed(_))).count()
+    }
+
+    // 有失败或一首都没成功时视为整体失败，供命令行返回非零退出码
+    pub fn check(&self) -> Result<()> {
+        let (succeeded, failed) = (self.succeeded(), self.failed());
+        if failed > 0 || succeeded == 0 {
+            return Err(AppError::BatchIncomplete { succeeded, not_found: self.not_found(), failed });
+        }
+        Ok(())
+    }
+}
+
+async fn resolve_entry(client: &Client, entry: &BatchEntry) -> Result<Option<Song>> {
+    match entry {
+        BatchEntry::Id(id) => Ok(api::get_song(client, &id.to_string()).await?.0),
+        BatchEntry::Mid(mid) => Ok(api::get_song(client, mid).await?.0),
+        BatchEntry::Query { artist, title } => {
+            let (songs, _) = api::search_song(client, &format!("{} {}", artist, title)).await?;
+            Ok(songs.into_iter().next())
+        },
+        BatchEntry::Track { song, .. } | BatchEntry::Song(song) => Ok(Some(song.clone())),
+    }
+}
+
+async fn process_entry(client: &Client, entry: &BatchEntry, format: LyricFormat, options: &SaveOptions) -> BatchOutcome {
+    let result = async {
+        let song = resolve_entry(client, entry).await?.ok_or(AppError::SongNotFound)?;
+        download_lyrics_as(client, &song, &entry.file_name(&song), format, options).await?.0.ok_or(AppError::LyricNotFound)
+    }.await;
+
+    match result {
+        Ok(saved) => BatchOutcome::Succeeded(saved),
+        Err(AppError::SongNotFound | AppError::LyricNotFound) => BatchOutcome::NotFound,
+        Err(e) => BatchOutcome::Failed(e.to_string()),
+    }
+}
+
+pub async fn run_batch(client: &Client, entries: Vec<BatchEntry>, format: LyricFormat, options: &SaveOptions, parallelism: usize) -> BatchReport {
+    run_batch_with_progress(client, entries, format, options, parallelism, |_, _, _| {}).await
+}
+
+pub async fn run_batch_with_progress(
+    client: &Client,
+    entries: Vec<BatchEntry>,
+    format: LyricFormat,
+    options: &SaveOptions,
+    parallelism: usize,
+    mut on_progress: impl FnMut(BatchProgress, &BatchEntry, &BatchOutcome),
+) -> BatchReport {
+    let semaphore = Arc::new(Semaphore::new(parallelism.max(1)));
+    let mut tasks = JoinSet::new();
+
+    for (index, entry) in entries.iter().cloned().enumerate() {
+        let client = client.clone();
+        let options = options.clone();
+        let semaphore = Arc::clone(&semaphore);
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            (index, process_entry(&client, &entry, format, &options).await)
+        });
+    }
+
+    let mut outcomes: Vec<Option<BatchOutcome>> = entries.iter().map(|_| None).collect();
+    let mut progress = BatchProgress { done: 0, total: entries.len() };
+    while let Some(joined) = tasks.join_next().await {
+        progress.done += 1;
+        if let Ok((index, outcome)) = joined {
+            on_progress(progress, &entries[index], &outcome);
+            outcomes[index] = Some(outcome);
+        }
+    }
+
+    let results = entries.into_iter()
+        .zip(outcomes)
+        .map(|(entry, outcome)| (entry, outcome.unwrap_or_else(|| BatchOutcome::Failed("任务异常终止".to_string()))))
+        .collect();
+    BatchReport { results }
+}
+
+pub fn album_dir(album: &AlbumDetail, options: &SaveOptions) -> PathBuf {
+    options.path_for(&utils::create_safe_dirname(&album.singer_names(), &album.name))
+}
+
+pub fn album_entries(album: &AlbumDetail) -> Vec<BatchEntry> {
+    let last = album.tracks.iter().map(|t| t.number).max().unwrap_or(0);
+    let width = last.to_string().len().max(2);
+
+    album.tracks.iter()
+        .map(|track| BatchEntry::Track { number: track.number, width, song: track.song.clone() })
+        .collect()
+}
+
+pub async fn run_album(
+    client: &Client,
+    album: &AlbumDetail,
+    format: LyricFormat,
+    options: &SaveOptions,
+    parallelism: usize,
+    on_progress: impl FnMut(BatchProgress, &BatchEntry, &BatchOutcome),
+) -> BatchReport {
+    let options = SaveOptions { output_dir: album_dir(album, options), ..options.clone() };
+    run_batch_with_progress(client, album_entries(album), format, &options, parallelism, on_progress).await
+}
+
+pub fn playlist_dir(playlist: &Playlist, options: &SaveOptions) -> PathBuf {
+    options.path_for(&utils::create_safe_dirname(&playlist.creator, &playlist.title))
+}
+
+pub fn playlist_entries(playlist: &Playlist) -> Vec<BatchEntry> {
+    // 歌单里可能有同名同歌手的歌曲，按歌单顺序编号避免文件名冲突
+    let width = playlist.songs.len().to_string().len().max(2);
+
+    playlist.songs.iter()
+        .zip(1..)
+        .map(|(song, number)| BatchEntry::Track { number, width, song: song.clone() })
+        .collect()
+}
+
+pub async fn run_playlist(
+    client: &Client,
+    playlist: &Playlist,
+    format: LyricFormat,
+    options: &SaveOptions,
+    parallelism: usize,
+    on_progress: impl FnMut(BatchProgress, &BatchEntry, &BatchOutcome),
+) -> BatchReport {
+    let options = SaveOptions { output_dir: playlist_dir(playlist, options), ..options.clone() };
+    run_batch_with_progress(client, playlist_entries(playlist), format, &options, parallelism, on_progress).await
+}
+
+fn song_key(song: &Song) -> (String, Vec<String>) {
+    let name = song.name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let singers = song.singer.iter()
+        .map(|s| if s.mid.is_empty() { s.name.clone() } else { s.mid.clone() })
+        .collect();
+    (name, singers)
+}
+
+// 同一首歌收录在多张专辑里时会有不同的 ID，按歌名和歌手去重
+pub fn dedup_songs(songs: Vec<Song>) -> Vec<Song> {
+    let mut seen = HashSet::new();
+    songs.into_iter().filter(|song| seen.insert(song_key(song))).collect()
+}
+
+pub fn singer_dir(singer_name: &str, options: &SaveOptions) -> PathBuf {
+    options.path_for(&utils::create_safe_dirname("", singer_name))
+}
+
+// songs 应已经过 dedup_songs 去重，这里不再重复处理
+pub async fn run_singer(
+    client: &Client,
+    singer_name: &str,
+    songs: Vec<Song>,
+    format: LyricFormat,
+    options: &SaveOptions,
+    parallelism: usize,
+    on_progress: impl FnMut(BatchProgress, &BatchEntry, &BatchOutcome),
+) -> BatchReport {
+    let options = SaveOptions { output_dir: singer_dir(singer_name, options), ..options.clone() };
+    let entries = songs.into_iter().map(BatchEntry::Song).collect();
+    run_batch_with_progress(client, entries, format, &options, parallelism, on_progress).await
+}
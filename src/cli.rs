@@ -0,0 +1,474 @@
+use clap::{Args, Parser, Subcommand};
+use reqwest::Client;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write, stdout};
+use std::path::{Path, PathBuf};
+
+use qrcdownloader::{AppError, AssLayout, AssProfile, KaraokeEffect, Result, ConvertOptions, LyricFormat, SaveOptions, download_lyrics, encrypt_lyrics, log_info, log_success, log_warn, log_error};
+use qrcdownloader::decrypto::{LyricsFileKind, lyrics_file_reader};
+use qrcdownloader::align::{DEFAULT_TOLERANCE_MS, align};
+use qrcdownloader::convert;
+use qrcdownloader::api::{SearchQuery, SearchType, search, get_song, get_album_songs, get_all_singer_songs, get_playlist, parse_playlist_id};
+use qrcdownloader::batch::{self, BatchEntry, BatchOutcome, BatchProgress, BatchReport};
+use qrcdownloader::lrc::{EnhancedLrcOptions, TimePrecision};
+
+#[derive(Debug, Parser)]
+#[command(name = "qrcdownloader", version, about = "从 QQ 音乐服务器下载歌词，不带子命令时进入交互模式")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 按关键词搜索歌曲、歌手或专辑
+    Search {
+        keyword: String,
+        /// 页码，从 1 开始
+        #[arg(short, long, default_value_t = 1)]
+        page: u32,
+        /// 每页结果数
+        #[arg(short = 'n', long, default_value_t = 20)]
+        page_size: u32,
+        /// 搜索类型: song, singer, album 或 lyric (按歌词内容搜索)
+        #[arg(short = 't', long = "type", default_value = "song")]
+        search_type: SearchType,
+    },
+    /// 通过歌曲 ID 或 MID 下载歌词
+    Fetch(FetchArgs),
+    /// 从列表文件批量下载歌词，每行一个 ID、MID 或 "艺人 - 歌名"
+    Batch {
+        file: PathBuf,
+        #[arg(short, long, default_value = "lrc")]
+        format: LyricFormat,
+        /// 同时进行的请求数
+        #[arg(short = 'j', long, default_value_t = batch::DEFAULT_PARALLELISM)]
+        parallel: usize,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// 下载整张专辑的歌词到以专辑命名的文件夹
+    Album {
+        /// 专辑 MID
+        mid: String,
+        #[arg(short, long, default_value = "lrc")]
+        format: LyricFormat,
+        /// 同时进行的请求数
+        #[arg(short = 'j', long, default_value_t = batch::DEFAULT_PARALLELISM)]
+        parallel: usize,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// 下载歌手全部歌曲的歌词，收录在多张专辑中的同一首歌只下载一次
+    Singer {
+        /// 歌手名，取搜索结果的第一位歌手
+        #[arg(required_unless_present = "mid", conflicts_with = "mid")]
+        name: Option<String>,
+        /// 歌手 MID
+        #[arg(long)]
+        mid: Option<String>,
+        #[arg(short, long, default_value = "lrc")]
+        format: LyricFormat,
+        /// 同时进行的请求数
+        #[arg(short = 'j', long, default_value_t = batch::DEFAULT_PARALLELISM)]
+        parallel: usize,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// 下载 QQ 音乐歌单中所有歌曲的歌词
+    Playlist {
+        /// 歌单 ID (disstid) 或分享链接
+        playlist: String,
+        #[arg(short, long, default_value = "lrc")]
+        format: LyricFormat,
+        /// 同时进行的请求数
+        #[arg(short = 'j', long, default_value_t = batch::DEFAULT_PARALLELISM)]
+        parallel: usize,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// 转换本地的 QRC、LRC 或 ASS 卡拉 OK 歌词文件
+    Convert {
+        input: PathBuf,
+        /// 目标格式: lrc, elrc, qrc, ass, srt, vtt 或 ttml
+        #[arg(short, long)]
+        to: LyricFormat,
+        /// 输出文件，省略时写到标准输出
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// 翻译歌词文件 (LRC)
+        #[arg(long, value_name = "FILE")]
+        trans: Option<PathBuf>,
+        /// 罗马音歌词文件 (QRC 或 LRC)
+        #[arg(long, value_name = "FILE")]
+        roma: Option<PathBuf>,
+        #[arg(long)]
+        overwrite: bool,
+        #[command(flatten)]
+        convert: ConvertArgs,
+    },
+    /// 将明文歌词加密为下载接口使用的十六进制格式
+    Encrypt {
+        file: PathBuf,
+        /// 输出文件，省略时写到标准输出
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long)]
+        overwrite: bool,
+    },
+    /// 解密 QRC 歌词: 下载接口的十六进制内容、原始密文或 QQ 音乐客户端缓存的 .qrc 文件
+    Decrypt {
+        file: PathBuf,
+        /// 输出文件，省略时写到标准输出
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// 按客户端本地缓存格式解密 (默认自动识别)
+        #[arg(long)]
+        local: bool,
+        #[arg(long)]
+        overwrite: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+#[group(id = "song", required = true, multiple = false)]
+pub struct FetchArgs {
+    #[arg(long, group = "song")]
+    pub id: Option<u64>,
+    #[arg(long, group = "song")]
+    pub mid: Option<String>,
+    /// 歌词格式: lrc, elrc, qrc, ass, srt, vtt 或 ttml
+    #[arg(short, long, default_value = "lrc")]
+    pub format: LyricFormat,
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct OutputArgs {
+    /// 保存歌词的目录
+    #[arg(short = 'o', long, default_value = ".")]
+    pub output_dir: PathBuf,
+    /// 覆盖已存在的文件
+    #[arg(long)]
+    pub overwrite: bool,
+    /// 转换为其他格式时同时保存原始 QRC 歌词
+    #[arg(long)]
+    pub keep_qrc: bool,
+    #[command(flatten)]
+    pub convert: ConvertArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct ConvertArgs {
+    /// 将翻译和罗马音按时间合并进主歌词，而不是单独保存
+    #[arg(long)]
+    pub merge: bool,
+    /// 增强型 LRC 使用毫秒精度的时间标签
+    #[arg(long)]
+    pub millis: bool,
+    /// 增强型 LRC 不输出最后一个字的结束时间标签
+    #[arg(long)]
+    pub no_end_tag: bool,
+    #[command(flatten)]
+    pub ass: AssArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct AssArgs {
+    /// ASS 样式配置文件 (JSON)
+    #[arg(long, value_name = "FILE")]
+    pub ass_style: Option<PathBuf>,
+    /// ASS 字体，应用于原文、翻译和罗马音
+    #[arg(long, value_name = "FONT")]
+    pub ass_font: Option<String>,
+    /// ASS 原文字号
+    #[arg(long, value_name = "SIZE")]
+    pub ass_font_size: Option<u32>,
+    /// ASS 原文主要颜色，如 &H00FFFFFF
+    #[arg(long, value_name = "COLOUR")]
+    pub ass_primary_colour: Option<String>,
+    /// ASS 原文卡拉 OK 未唱颜色
+    #[arg(long, value_name = "COLOUR")]
+    pub ass_secondary_colour: Option<String>,
+    /// ASS 原文对齐方式 (小键盘布局 1-9)
+    #[arg(long, value_name = "N")]
+    pub ass_alignment: Option<u8>,
+    /// ASS 分辨率，如 1920x1080
+    #[arg(long, value_name = "WxH")]
+    pub ass_resolution: Option<String>,
+    /// ASS 卡拉 OK 效果: k, kf (扫过) 或 ko (描边)
+    #[arg(long, value_name = "MODE")]
+    pub ass_karaoke: Option<KaraokeEffect>,
+    /// 每行淡入时长 (毫秒)
+    #[arg(long, value_name = "MS")]
+    pub ass_fade_in: Option<u64>,
+    /// 每行淡出时长 (毫秒)
+    #[arg(long, value_name = "MS")]
+    pub ass_fade_out: Option<u64>,
+    /// 每行提前显示的时长 (毫秒)
+    #[arg(long, value_name = "MS")]
+    pub ass_pre_display: Option<u64>,
+    /// ASS 布局: single 或 two-row (双行交替的 KTV 布局)
+    #[arg(long, value_name = "LAYOUT")]
+    pub ass_layout: Option<AssLayout>,
+}
+
+impl AssArgs {
+    pub fn profile(&self) -> Result<AssProfile> {
+        let mut profile = match &self.ass_style {
+            Some(path) => AssProfile::load(path)?,
+            None => AssProfile::default(),
+        };
+
+        if let Some(font) = &self.ass_font {
+            profile.set_font_name(font);
+        }
+        if let Some(size) = self.ass_font_size {
+            profile.original.font_size = size;
+        }
+        if let Some(colour) = &self.ass_primary_colour {
+            profile.original.primary_colour = colour.clone();
+        }
+        if let Some(colour) = &self.ass_secondary_colour {
+            profile.original.secondary_colour = colour.clone();
+        }
+        if let Some(alignment) = self.ass_alignment {
+            profile.original.alignment = alignment;
+        }
+        if let Some(effect) = self.ass_karaoke {
+            profile.karaoke.effect = effect;
+        }
+        if let Some(fade_in) = self.ass_fade_in {
+            profile.karaoke.fade_in_ms = fade_in;
+        }
+        if let Some(fade_out) = self.ass_fade_out {
+            profile.karaoke.fade_out_ms = fade_out;
+        }
+        if let Some(pre_display) = self.ass_pre_display {
+            profile.karaoke.pre_display_ms = pre_display;
+        }
+        if let Some(layout) = self.ass_layout {
+            profile.layout = layout;
+        }
+        if let Some(resolution) = &self.ass_resolution {
+            let (x, y) = resolution.split_once(['x', 'X']).ok_or(AppError::InvalidInput)?;
+            profile.play_res_x = x.trim().parse().map_err(|_| AppError::InvalidInput)?;
+            profile.play_res_y = y.trim().parse().map_err(|_| AppError::InvalidInput)?;
+        }
+        Ok(profile)
+    }
+}
+
+impl ConvertArgs {
+    pub fn convert_options(&self) -> Result<ConvertOptions> {
+        Ok(ConvertOptions {
+            merge_tracks: self.merge,
+            enhanced_lrc: EnhancedLrcOptions {
+                precision: if self.millis { TimePrecision::Millisecond } else { TimePrecision::Centisecond },
+                include_end_tag: !self.no_end_tag,
+            },
+            ass_profile: self.ass.profile()?,
+        })
+    }
+}
+
+impl OutputArgs {
+    pub fn save_options(&self) -> Result<SaveOptions> {
+        Ok(SaveOptions {
+            output_dir: self.output_dir.clone(),
+            overwrite: self.overwrite,
+            keep_qrc: self.keep_qrc,
+            convert: self.convert.convert_options()?,
+        })
+    }
+}
+
+pub async fn run(client: &Client, command: Command) -> Result<()> {
+    match command {
+        Command::Search { keyword, page, page_size, search_type } => {
+            let query = SearchQuery { keyword, page, page_size, search_type };
+            search_songs(client, &query).await
+        },
+        Command::Fetch(args) => fetch(client, &args).await,
+        Command::Batch { file, format, parallel, output } => run_batch(client, &file, format, parallel, &output.save_options()?).await,
+        Command::Album { mid, format, parallel, output } => download_album(client, &mid, format, parallel, &output.save_options()?).await,
+        Command::Singer { name, mid, format, parallel, output } => {
+            download_singer(client, name.as_deref(), mid.as_deref(), format, parallel, &output.save_options()?).await
+        },
+        Command::Playlist { playlist, format, parallel, output } => download_playlist(client, &playlist, format, parallel, &output.save_options()?).await,
+        Command::Convert { input, to, output, trans, roma, overwrite, convert } => {
+            convert_file(&input, to, output, trans, roma, overwrite, &convert.convert_options()?)
+        },
+        Command::Encrypt { file, output, overwrite } => encrypt(&file, output, overwrite),
+        Command::Decrypt { file, output, local, overwrite } => decrypt(&file, output, local, overwrite),
+    }
+}
+
+// 输出到文件时先写入同目录下的临时文件，全部成功后再改名，失败时不会留下半截文件或破坏已有文件
+fn write_output(output: Option<PathBuf>, overwrite: bool, write: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<()> {
+    let Some(path) = output else {
+        let mut writer = stdout().lock();
+        write(&mut writer)?;
+        writer.flush()?;
+        return Ok(());
+    };
+    if !overwrite && path.exists() {
+        return Err(AppError::FileExists(path.display().to_string()));
+    }
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let result = File::create(&temp_path).map_err(AppError::from).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.flush()?;
+        Ok(fs::rename(&temp_path, &path)?)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn convert_file(input: &Path, to: LyricFormat, output: Option<PathBuf>, trans: Option<PathBuf>, roma: Option<PathBuf>, overwrite: bool, options: &ConvertOptions) -> Result<()> {
+    let original = convert::parse_lyric(&fs::read_to_string(input)?)?;
+    let translation = trans.map(|path| fs::read_to_string(path).map_err(AppError::from).and_then(|c| convert::parse_lyric(&c))).transpose()?;
+    let romanization = roma.map(|path| fs::read_to_string(path).map_err(AppError::from).and_then(|c| convert::parse_lyric(&c))).transpose()?;
+    let aligned = align(&original, translation.as_ref(), romanization.as_ref(), DEFAULT_TOLERANCE_MS);
+
+    write_output(output, overwrite, |mut writer| convert::write_aligned(&mut writer, &aligned, to, options))
+}
+
+async fn search_songs(client: &Client, query: &SearchQuery) -> Result<()> {
+    let (page, _) = search(client, query).await?;
+    if page.is_empty() {
+        return Err(AppError::SongNotFound);
+    }
+
+    eprintln!("共 {} 条结果, 第 {}/{} 页", page.total, page.page, page.page_count());
+    for song in &page.songs {
+        let duration = song.duration_text().unwrap_or_default();
+        println!("{}\t{}\t{}\t{}\t{}\t{}", song.id, song.mid, song.name, song.artists(), song.album.name, duration);
+    }
+    for singer in &page.singers {
+        println!("{}\t{}\t{}\t{}\t{}", singer.id, singer.mid, singer.name, singer.song_count, singer.album_count);
+    }
+    for album in &page.albums {
+        println!("{}\t{}\t{}\t{}\t{}\t{}", album.id, album.mid, album.name, album.singer_name, album.song_count, album.public_time);
+    }
+    Ok(())
+}
+
+async fn fetch(client: &Client, args: &FetchArgs) -> Result<()> {
+    let id_or_mid = match (&args.id, &args.mid) {
+        (Some(id), _) => id.to_string(),
+        (None, Some(mid)) => mid.clone(),
+        (None, None) => return Err(AppError::InvalidInput),
+    };
+
+    let (song, _) = get_song(client, &id_or_mid).await?;
+    let song = song.ok_or(AppError::SongNotFound)?;
+    log_info!("正在获取 {} 歌词: {}", args.format.label(), song.name);
+
+    let (saved, _) = download_lyrics(client, &song, args.format, &args.output.save_options()?).await?;
+    for path in saved.ok_or(AppError::LyricNotFound)? {
+        log_success!("歌词已保存至: {}", path.display());
+    }
+    Ok(())
+}
+
+async fn run_batch(client: &Client, file: &Path, format: LyricFormat, parallel: usize, options: &SaveOptions) -> Result<()> {
+    let entries = batch::parse_batch_list(&fs::read_to_string(file)?);
+    if entries.is_empty() {
+        return Err(AppError::InvalidInput);
+    }
+    log_info!("共 {} 首歌曲，并发数 {}", entries.len(), parallel);
+
+    let report = batch::run_batch_with_progress(client, entries, format, options, parallel, print_batch_progress).await;
+    print_batch_summary(&report);
+    report.check()
+}
+
+async fn download_album(client: &Client, mid: &str, format: LyricFormat, parallel: usize, options: &SaveOptions) -> Result<()> {
+    let (album, _) = get_album_songs(client, mid).await?;
+    let album = album.ok_or(AppError::AlbumNotFound)?;
+    log_info!("专辑: {} - {}，共 {} 首歌曲", album.singer_names(), album.name, album.tracks.len());
+
+    let report = batch::run_album(client, &album, format, options, parallel, print_batch_progress).await;
+    print_batch_summary(&report);
+    report.check()
+}
+
+async fn download_singer(client: &Client, name: Option<&str>, mid: Option<&str>, format: LyricFormat, parallel: usize, options: &SaveOptions) -> Result<()> {
+    let (singer_mid, singer_name) = match (mid, name) {
+        (Some(mid), _) => (mid.to_string(), None),
+        (None, Some(name)) => {
+            let query = SearchQuery { search_type: SearchType::Singer, ..SearchQuery::new(name) };
+            let (page, _) = search(client, &query).await?;
+            let singer = page.singers.into_iter().next().ok_or(AppError::SingerNotFound)?;
+            (singer.mid, Some(singer.name))
+        },
+        (None, None) => return Err(AppError::InvalidInput),
+    };
+
+    let (songs, _) = get_all_singer_songs(client, &singer_mid).await?;
+    let songs = songs.ok_or(AppError::SingerNotFound)?;
+    let singer_name = singer_name
+        .or_else(|| songs.iter().flat_map(|s| &s.singer).find(|s| s.mid == singer_mid).map(|s| s.name.clone()))
+        .unwrap_or_else(|| singer_mid.clone());
+    let total = songs.len();
+    let songs = batch::dedup_songs(songs);
+    log_info!("歌手: {}，共 {} 首歌曲，去重后 {} 首", singer_name, total, songs.len());
+
+    let report = batch::run_singer(client, &singer_name, songs, format, options, parallel, print_batch_progress).await;
+    print_batch_summary(&report);
+    report.check()
+}
+
+async fn download_playlist(client: &Client, playlist: &str, format: LyricFormat, parallel: usize, options: &SaveOptions) -> Result<()> {
+    let disstid = parse_playlist_id(playlist).ok_or(AppError::InvalidInput)?;
+    let (playlist, _) = get_playlist(client, disstid).await?;
+    let playlist = playlist.ok_or(AppError::PlaylistNotFound)?;
+    log_info!("歌单: {} (创建者: {})，共 {} 首歌曲", playlist.title, playlist.creator, playlist.songs.len());
+
+    let report = batch::run_playlist(client, &playlist, format, options, parallel, print_batch_progress).await;
+    print_batch_summary(&report);
+    report.check()
+}
+
+pub fn print_batch_progress(progress: BatchProgress, entry: &BatchEntry, outcome: &BatchOutcome) {
+    let prefix = format!("[{}/{}] {}", progress.done, progress.total, entry);
+    match outcome {
+        BatchOutcome::Succeeded(saved) => {
+            for path in saved {
+                log_success!("{}: 已保存至 {}", prefix, path.display());
+            }
+        },
+        BatchOutcome::NotFound => log_warn!("{}: 未找到歌曲或歌词", prefix),
+        BatchOutcome::Failed(message) => log_error!("{}: {}", prefix, message),
+    }
+}
+
+pub fn print_batch_summary(report: &BatchReport) {
+    println!("\n-----------------------");
+    println!("成功: {}", report.succeeded());
+    println!("未找到: {}", report.not_found());
+    println!("失败: {}", report.failed());
+    println!("-----------------------");
+}
+
+fn encrypt(file: &Path, output: Option<PathBuf>, overwrite: bool) -> Result<()> {
+    let encrypted = encrypt_lyrics(&fs::read_to_string(file)?)?;
+
+    write_output(output, overwrite, |writer| Ok(writer.write_all(encrypted.as_bytes())?))
+}
+
+fn decrypt(file: &Path, output: Option<PathBuf>, local: bool, overwrite: bool) -> Result<()> {
+    let input = BufReader::new(File::open(file)?);
+    let mut reader = lyrics_file_reader(input, local.then_some(LyricsFileKind::Local))?;
+    write_output(output, overwrite, |writer| {
+        io::copy(&mut reader, writer)?;
+        Ok(())
+    })
+}
@@ -0,0 +1,128 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::align::{self, AlignedLyric};
+use crate::api::QqLyricsResponse;
+use crate::ass_style::AssProfile;
+use crate::lrc::EnhancedLrcOptions;
+use crate::lyric::{Lyric, LyricMetadata};
+use crate::{AppError, Result, ass_converter, lrc, qrc, subtitle, ttml};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LyricFormat {
+    Lrc,
+    EnhancedLrc,
+    Qrc,
+    Ass,
+    Srt,
+    WebVtt,
+    Ttml,
+}
+
+impl LyricFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LyricFormat::Lrc | LyricFormat::EnhancedLrc => "lrc",
+            LyricFormat::Qrc => "qrc",
+            LyricFormat::Ass => "ass",
+            LyricFormat::Srt => "srt",
+            LyricFormat::WebVtt => "vtt",
+            LyricFormat::Ttml => "ttml",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LyricFormat::Lrc => "LRC",
+            LyricFormat::EnhancedLrc => "增强型 LRC",
+            LyricFormat::Qrc => "QRC",
+            LyricFormat::Ass => "ASS",
+            LyricFormat::Srt => "SRT",
+            LyricFormat::WebVtt => "WebVTT",
+            LyricFormat::Ttml => "TTML",
+        }
+    }
+}
+
+impl FromStr for LyricFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "lrc" => Ok(LyricFormat::Lrc),
+            "elrc" | "enhanced-lrc" => Ok(LyricFormat::EnhancedLrc),
+            "qrc" => Ok(LyricFormat::Qrc),
+            "ass" => Ok(LyricFormat::Ass),
+            "srt" => Ok(LyricFormat::Srt),
+            "vtt" | "webvtt" => Ok(LyricFormat::WebVtt),
+            "ttml" => Ok(LyricFormat::Ttml),
+            _ => Err(AppError::InvalidInput),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    pub merge_tracks: bool,
+    pub enhanced_lrc: EnhancedLrcOptions,
+    pub ass_profile: AssProfile,
+}
+
+pub fn parse_lyric(content: &str) -> Result<Lyric> {
+    if content.lines().any(|line| line.trim().eq_ignore_ascii_case("[events]")) {
+        return ass_converter::parse_ass(content);
+    }
+
+    let is_qrc = content.trim_start_matches('\u{feff}').trim_start().starts_with('<')
+        || content.lines().any(|line| {
+            line.strip_prefix('[')
+                .and_then(|rest| rest.split_once(']'))
+                .and_then(|(tag, _)| tag.split_once(','))
+                .is_some_and(|(start, _)| !start.is_empty() && start.chars().all(|c| c.is_ascii_digit()))
+        });
+
+    if is_qrc { qrc::parse(content) } else { lrc::parse(content) }
+}
+
+pub(crate) fn parse_optional<F>(content: Option<&str>, parse: F) -> Result<Option<Lyric>>
+where
+    F: Fn(&str) -> Result<Lyric>,
+{
+    content.filter(|c| !c.trim().is_empty()).map(parse).transpose()
+}
+
+pub fn align_qrc_tracks(lyrics: &QqLyricsResponse) -> Result<AlignedLyric> {
+    let original = qrc::parse(&lyrics.lyrics)?;
+    let translation = parse_optional(Some(&lyrics.trans), lrc::parse)?;
+    let romanization = parse_optional(Some(&lyrics.roma), qrc::parse)?;
+    Ok(align::align(&original, translation.as_ref(), romanization.as_ref(), align::DEFAULT_TOLERANCE_MS))
+}
+
+pub fn write_aligned(writer: &mut impl Write, aligned: &AlignedLyric, format: LyricFormat, options: &ConvertOptions) -> Result<()> {
+    match format {
+        LyricFormat::Lrc => writer.write_all(lrc::write_aligned(aligned).as_bytes())?,
+        LyricFormat::EnhancedLrc => writer.write_all(lrc::write_enhanced(&aligned.original(), &options.enhanced_lrc).as_bytes())?,
+        LyricFormat::Qrc => writer.write_all(qrc::write_aligned(aligned).as_bytes())?,
+        LyricFormat::Ass => ass_converter::write_ass(writer, aligned, &options.ass_profile)?,
+        LyricFormat::Srt => writer.write_all(subtitle::write_srt(aligned).as_bytes())?,
+        LyricFormat::WebVtt => writer.write_all(subtitle::write_webvtt(aligned, true).as_bytes())?,
+        LyricFormat::Ttml => writer.write_all(ttml::write_ttml(aligned)?.as_bytes())?,
+    }
+    Ok(())
+}
+
+pub fn write_converted(writer: &mut impl Write, lyrics: &QqLyricsResponse, metadata: &LyricMetadata, format: LyricFormat, options: &ConvertOptions) -> Result<()> {
+    let with_tracks = options.merge_tracks || matches!(format, LyricFormat::Srt | LyricFormat::WebVtt | LyricFormat::Ttml);
+    if format == LyricFormat::Qrc && !with_tracks {
+        writer.write_all(lyrics.lyrics.as_bytes())?;
+        return Ok(());
+    }
+
+    let mut aligned = if with_tracks {
+        align_qrc_tracks(lyrics)?
+    } else {
+        AlignedLyric::from(qrc::parse(&lyrics.lyrics)?)
+    };
+    aligned.metadata.merge_missing(metadata);
+    write_aligned(writer, &aligned, format, options)
+}
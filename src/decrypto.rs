@@ -1,11 +1,25 @@
-use std::io::Read;
-use flate2::read::ZlibDecoder;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use flate2::{Compression, Decompress, FlushDecompress, Status};
+use flate2::write::ZlibEncoder;
+use once_cell::sync::Lazy;
 use crate::AppError;
 
 pub const ENCRYPT: u32 = 1;
 pub const DECRYPT: u32 = 0;
 
 pub const QQ_KEY: &[u8] = b"!@#)(*$%123ZXC!@!@#)(NHL";
+
+pub const LOCAL_QRC_HEADER_LEN: usize = 11;
+pub const QMC1_KEY: [u8; 128] = [
+    0xc3, 0x4a, 0xd6, 0xca, 0x90, 0x67, 0xf7, 0x52, 0xd8, 0xa1, 0x66, 0x62, 0x9f, 0x5b, 0x09, 0x00,
+    0xc3, 0x5e, 0x95, 0x23, 0x9f, 0x13, 0x11, 0x7e, 0xd8, 0x92, 0x3f, 0xbc, 0x90, 0xbb, 0x74, 0x0e,
+    0xc3, 0x47, 0x74, 0x3d, 0x90, 0xaa, 0x3f, 0x51, 0xd8, 0xf4, 0x11, 0x84, 0x9f, 0xde, 0x95, 0x1d,
+    0xc3, 0xc6, 0x09, 0xd5, 0x9f, 0xfa, 0x66, 0xf9, 0xd8, 0xf0, 0xf7, 0xa0, 0x90, 0xa1, 0xd6, 0xf3,
+    0xc3, 0xf3, 0xd6, 0xa1, 0x90, 0xa0, 0xf7, 0xf0, 0xd8, 0xf9, 0x66, 0xfa, 0x9f, 0xd5, 0x09, 0xc6,
+    0xc3, 0x1d, 0x95, 0xde, 0x9f, 0x84, 0x11, 0xf4, 0xd8, 0x51, 0x3f, 0xaa, 0x90, 0x3d, 0x74, 0x47,
+    0xc3, 0x0e, 0x74, 0xbb, 0x90, 0xbc, 0x3f, 0x92, 0xd8, 0x7e, 0x11, 0x13, 0x9f, 0x23, 0x95, 0x5e,
+    0xc3, 0x00, 0x09, 0x5b, 0x9f, 0x62, 0x66, 0xa1, 0xd8, 0x52, 0xf7, 0x67, 0x90, 0xca, 0xd6, 0x4a
+];
 pub const SBOX1: [u8; 64] = [
     14,  4,  13,  1,   2, 15,  11,  8,   3, 10,   6, 12,   5,  9,   0,  7,
      0, 15,   7,  4,  14,  2,  13,  1,  10,  6,  12, 11,   9,  5,   3,  8,
@@ -78,7 +92,60 @@ pub const fn sbox_bit(a: u8) -> usize {
     ((a & 0x20) | ((a & 0x1f) >> 1) | ((a & 0x01) << 4)) as usize
 }
 
-pub fn key_schedule(key: &[u8], schedule: &mut [Vec<u8>], mode: u32) {
+pub type KeySchedule = [[u8; 6]; 16];
+pub type TripleDesSchedule = [KeySchedule; 3];
+
+const SBOXES: [&[u8; 64]; 8] = [&SBOX1, &SBOX2, &SBOX3, &SBOX4, &SBOX5, &SBOX6, &SBOX7, &SBOX8];
+
+struct DesTables {
+    ip: [[u64; 256]; 8],
+    inv_ip: [[u64; 256]; 8],
+    expansion: [[u64; 256]; 4],
+    sp: [[u32; 64]; 8],
+}
+
+static TABLES: Lazy<DesTables> = Lazy::new(DesTables::build);
+static ENCRYPT_SCHEDULE: Lazy<TripleDesSchedule> = Lazy::new(|| triple_des_schedule(QQ_KEY, ENCRYPT));
+static DECRYPT_SCHEDULE: Lazy<TripleDesSchedule> = Lazy::new(|| triple_des_schedule(QQ_KEY, DECRYPT));
+
+impl DesTables {
+    // 置换都是按位线性的，逐字节套用原始的逐位实现即可得到查找表
+    fn build() -> Self {
+        let mut tables = DesTables { ip: [[0; 256]; 8], inv_ip: [[0; 256]; 8], expansion: [[0; 256]; 4], sp: [[0; 64]; 8] };
+
+        for byte in 0..8 {
+            for value in 0..256 {
+                let mut input = [0u8; 8];
+                input[byte] = value as u8;
+                let mut state = [0u32; 2];
+                ip(&mut state, &input);
+                tables.ip[byte][value] = ((state[0] as u64) << 32) | state[1] as u64;
+
+                let mut state = [0u32; 2];
+                state[byte / 4] = (value as u32) << (24 - 8 * (byte % 4));
+                let mut output = [0u8; 8];
+                inv_ip(&state, &mut output);
+                tables.inv_ip[byte][value] = u64::from_be_bytes(output);
+            }
+        }
+
+        for byte in 0..4 {
+            for value in 0..256 {
+                tables.expansion[byte][value] = expand((value as u32) << (24 - 8 * byte));
+            }
+        }
+
+        for (i, sbox) in SBOXES.iter().enumerate() {
+            for value in 0..64 {
+                tables.sp[i][value] = permute((sbox[sbox_bit(value as u8)] as u32) << (28 - 4 * i));
+            }
+        }
+
+        tables
+    }
+}
+
+pub fn key_schedule(key: &[u8], schedule: &mut KeySchedule, mode: u32) {
     let key_rnd_shift: [u32; 16] = [1, 1, 2, 2, 2, 2, 2, 2, 1, 2, 2, 2, 2, 2, 2, 1];
     let key_perm_c: [usize; 28] = [56, 48, 40, 32, 24, 16, 8, 0, 57, 49, 41, 33, 25, 17,
         9, 1, 58, 50, 42, 34, 26, 18, 10, 2, 59, 51, 43, 35];
@@ -106,9 +173,7 @@ pub fn key_schedule(key: &[u8], schedule: &mut [Vec<u8>], mode: u32) {
 
         let to_gen = if mode == DECRYPT { 15 - i } else { i };
 
-        for j in 0..6 {
-            schedule[to_gen][j] = 0;
-        }
+        schedule[to_gen].fill(0);
 
         for (j, &comp) in key_compression.iter().enumerate().take(24) {
             schedule[to_gen][j / 8] |= bit_num_intr(c, comp, 7 - (j % 8));
@@ -120,7 +185,7 @@ pub fn key_schedule(key: &[u8], schedule: &mut [Vec<u8>], mode: u32) {
     }
 }
 
-pub fn ip(state: &mut [u32; 2], input: &[u8]) {
+fn ip(state: &mut [u32; 2], input: &[u8]) {
     state[0] = bit_num(input, 57, 31) | bit_num(input, 49, 30) | bit_num(input, 41, 29) | bit_num(input, 33, 28) |
         bit_num(input, 25, 27) | bit_num(input, 17, 26) | bit_num(input, 9, 25) | bit_num(input, 1, 24) |
         bit_num(input, 59, 23) | bit_num(input, 51, 22) | bit_num(input, 43, 21) | bit_num(input, 35, 20) |
@@ -140,7 +205,7 @@ pub fn ip(state: &mut [u32; 2], input: &[u8]) {
         bit_num(input, 30, 3) | bit_num(input, 22, 2) | bit_num(input, 14, 1) | bit_num(input, 6, 0);
 }
 
-pub fn inv_ip(state: &[u32; 2], output: &mut [u8]) {
+fn inv_ip(state: &[u32; 2], output: &mut [u8]) {
     output[3] = bit_num_intr(state[1], 7, 7) | bit_num_intr(state[0], 7, 6) | bit_num_intr(state[1], 15, 5) |
         bit_num_intr(state[0], 15, 4) | bit_num_intr(state[1], 23, 3) | bit_num_intr(state[0], 23, 2) |
         bit_num_intr(state[1], 31, 1) | bit_num_intr(state[0], 31, 0);
@@ -174,8 +239,7 @@ pub fn inv_ip(state: &[u32; 2], output: &mut [u8]) {
         bit_num_intr(state[1], 24, 1) | bit_num_intr(state[0], 24, 0);
 }
 
-pub fn f_function(state: u32, key: &[u8]) -> u32 {
-    let mut lrg_state = [0u8; 6];
+fn expand(state: u32) -> u64 {
     let t1 = bit_num_intl(state, 31, 0) | ((state & 0xf0000000) >> 1) | bit_num_intl(state, 4, 5) |
         bit_num_intl(state, 3, 6) | ((state & 0x0f000000) >> 3) | bit_num_intl(state, 8, 11) |
         bit_num_intl(state, 7, 12) | ((state & 0x00f00000) >> 5) | bit_num_intl(state, 12, 17) |
@@ -186,30 +250,11 @@ pub fn f_function(state: u32, key: &[u8]) -> u32 {
         bit_num_intl(state, 23, 12) | ((state & 0x000000f0) << 11) | bit_num_intl(state, 28, 17) |
         bit_num_intl(state, 27, 18) | ((state & 0x0000000f) << 9) | bit_num_intl(state, 0, 23);
 
-    lrg_state[0] = ((t1 >> 24) & 0x000000ff) as u8;
-    lrg_state[1] = ((t1 >> 16) & 0x000000ff) as u8;
-    lrg_state[2] = ((t1 >> 8) & 0x000000ff) as u8;
-    lrg_state[3] = ((t2 >> 24) & 0x000000ff) as u8;
-    lrg_state[4] = ((t2 >> 16) & 0x000000ff) as u8;
-    lrg_state[5] = ((t2 >> 8) & 0x000000ff) as u8;
-
-    lrg_state[0] ^= key[0];
-    lrg_state[1] ^= key[1];
-    lrg_state[2] ^= key[2];
-    lrg_state[3] ^= key[3];
-    lrg_state[4] ^= key[4];
-    lrg_state[5] ^= key[5];
-
-    let mut result = ((SBOX1[sbox_bit(lrg_state[0] >> 2)] as u32) << 28) |
-        ((SBOX2[sbox_bit(((lrg_state[0] & 0x03) << 4) | (lrg_state[1] >> 4))] as u32) << 24) |
-        ((SBOX3[sbox_bit(((lrg_state[1] & 0x0f) << 2) | (lrg_state[2] >> 6))] as u32) << 20) |
-        ((SBOX4[sbox_bit(lrg_state[2] & 0x3f)] as u32) << 16) |
-        ((SBOX5[sbox_bit(lrg_state[3] >> 2)] as u32) << 12) |
-        ((SBOX6[sbox_bit(((lrg_state[3] & 0x03) << 4) | (lrg_state[4] >> 4))] as u32) << 8) |
-        ((SBOX7[sbox_bit(((lrg_state[4] & 0x0f) << 2) | (lrg_state[5] >> 6))] as u32) << 4) |
-        (SBOX8[sbox_bit(lrg_state[5] & 0x3f)] as u32);
-
-    result = bit_num_intl(result, 15, 0) | bit_num_intl(result, 6, 1) | bit_num_intl(result, 19, 2) |
+    (((t1 >> 8) as u64) << 24) | (t2 >> 8) as u64
+}
+
+fn permute(result: u32) -> u32 {
+    bit_num_intl(result, 15, 0) | bit_num_intl(result, 6, 1) | bit_num_intl(result, 19, 2) |
         bit_num_intl(result, 20, 3) | bit_num_intl(result, 28, 4) | bit_num_intl(result, 11, 5) |
         bit_num_intl(result, 27, 6) | bit_num_intl(result, 16, 7) | bit_num_intl(result, 0, 8) |
         bit_num_intl(result, 14, 9) | bit_num_intl(result, 22, 10) | bit_num_intl(result, 25, 11) |
@@ -219,28 +264,39 @@ pub fn f_function(state: u32, key: &[u8]) -> u32 {
         bit_num_intl(result, 26, 21) | bit_num_intl(result, 2, 22) | bit_num_intl(result, 8, 23) |
         bit_num_intl(result, 18, 24) | bit_num_intl(result, 12, 25) | bit_num_intl(result, 29, 26) |
         bit_num_intl(result, 5, 27) | bit_num_intl(result, 21, 28) | bit_num_intl(result, 10, 29) |
-        bit_num_intl(result, 3, 30) | bit_num_intl(result, 24, 31);
+        bit_num_intl(result, 3, 30) | bit_num_intl(result, 24, 31)
+}
+
+#[inline]
+fn f_function(tables: &DesTables, state: u32, key: &[u8; 6]) -> u32 {
+    let [b0, b1, b2, b3] = state.to_be_bytes();
+    let expanded = tables.expansion[0][b0 as usize] | tables.expansion[1][b1 as usize] |
+        tables.expansion[2][b2 as usize] | tables.expansion[3][b3 as usize];
+    let mixed = expanded ^ u64::from_be_bytes([0, 0, key[0], key[1], key[2], key[3], key[4], key[5]]);
 
-    result
+    tables.sp.iter().enumerate().fold(0, |result, (i, sp)| result | sp[((mixed >> (42 - 6 * i)) & 0x3f) as usize])
 }
 
-pub fn des_crypt(input: &[u8], output: &mut [u8], key: &[Vec<u8>]) {
-    let mut state = [0u32; 2];
-    
-    ip(&mut state, input);
+pub fn des_crypt(input: &[u8], output: &mut [u8], key: &KeySchedule) {
+    let tables = &*TABLES;
+    let permuted = input[..8].iter().enumerate().fold(0, |state, (i, &b)| state | tables.ip[i][b as usize]);
+    let mut left = (permuted >> 32) as u32;
+    let mut right = permuted as u32;
 
-    for key_item in key.iter().take(15) {
-        let t = state[1];
-        state[1] = f_function(state[1], key_item) ^ state[0];
-        state[0] = t;
+    for round_key in &key[..15] {
+        let t = right;
+        right = f_function(tables, right, round_key) ^ left;
+        left = t;
     }
 
-    state[0] ^= f_function(state[1], &key[15]);
+    left ^= f_function(tables, right, &key[15]);
 
-    inv_ip(&state, output);
+    let state = ((left as u64) << 32) | right as u64;
+    let result = state.to_be_bytes().iter().enumerate().fold(0, |out, (i, &b)| out | tables.inv_ip[i][b as usize]);
+    output[..8].copy_from_slice(&result.to_be_bytes());
 }
 
-pub fn triple_des_key_setup(key: &[u8], schedule: &mut [Vec<Vec<u8>>], mode: u32) {
+pub fn triple_des_key_setup(key: &[u8], schedule: &mut TripleDesSchedule, mode: u32) {
     if mode == ENCRYPT {
         key_schedule(&key[0..8], &mut schedule[0], mode);
         key_schedule(&key[8..16], &mut schedule[1], DECRYPT);
@@ -252,7 +308,13 @@ pub fn triple_des_key_setup(key: &[u8], schedule: &mut [Vec<Vec<u8>>], mode: u32
     }
 }
 
-pub fn triple_des_crypt(input: &[u8], output: &mut [u8], key: &[Vec<Vec<u8>>]) {
+pub fn triple_des_schedule(key: &[u8], mode: u32) -> TripleDesSchedule {
+    let mut schedule = [[[0u8; 6]; 16]; 3];
+    triple_des_key_setup(key, &mut schedule, mode);
+    schedule
+}
+
+pub fn triple_des_crypt(input: &[u8], output: &mut [u8], key: &TripleDesSchedule) {
     let mut temp1 = [0u8; 8];
     let mut temp2 = [0u8; 8];
     
@@ -262,53 +324,435 @@ pub fn triple_des_crypt(input: &[u8], output: &mut [u8], key: &[Vec<Vec<u8>>]) {
 }
 
 pub fn hex_string_to_byte_array(hex_string: &str) -> Result<Vec<u8>, AppError> {
-    (0..hex_string.len())
-        .step_by(2)
-        .filter_map(|i| {
-            if i + 2 <= hex_string.len() {
-                let byte_str = &hex_string[i..i+2];
-                match u8::from_str_radix(byte_str, 16) {
-                    Ok(byte) => Some(Ok(byte)),
-                    Err(e) => Some(Err(AppError::InvalidHex(e))),
-                }
-            } else {
-                None
-            }
-        })
-        .collect()
+    let digits = hex_string
+        .chars()
+        .enumerate()
+        .map(|(position, found)| found.to_digit(16).map(|d| d as u8).ok_or(AppError::InvalidHex { position, found }))
+        .collect::<Result<Vec<u8>, AppError>>()?;
+
+    if digits.len() % 2 != 0 {
+        return Err(AppError::OddHexLength(digits.len()));
+    }
+
+    Ok(digits.chunks_exact(2).map(|pair| (pair[0] << 4) | pair[1]).collect())
+}
+
+pub fn byte_array_to_hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+pub fn compress(data: &[u8]) -> Result<Vec<u8>, AppError> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+pub fn decrypt_lyrics(encrypted: &str) -> Result<String, AppError> {
+    read_lyrics(hex_lyrics_reader(encrypted.as_bytes()))
+}
+
+pub fn decrypt_lyrics_bytes(encrypted_bytes: &[u8]) -> Result<String, AppError> {
+    read_lyrics(binary_lyrics_reader(encrypted_bytes))
+}
+
+fn read_lyrics(mut reader: impl Read) -> Result<String, AppError> {
+    let mut decrypted = Vec::new();
+    reader.read_to_end(&mut decrypted)?;
+    String::from_utf8(decrypted).map_err(|source| AppError::InvalidUtf8 { position: source.utf8_error().valid_up_to(), source })
+}
+
+pub fn encrypt_lyrics(plain: &str) -> Result<String, AppError> {
+    let mut compressed = compress(plain.as_bytes())?;
+    compressed.resize(compressed.len().div_ceil(8) * 8, 0);
+
+    let mut encrypted_data = vec![0; compressed.len()];
+    for (chunk, output) in compressed.chunks(8).zip(encrypted_data.chunks_mut(8)) {
+        triple_des_crypt(chunk, output, &ENCRYPT_SCHEDULE);
+    }
+
+    Ok(byte_array_to_hex_string(&encrypted_data))
 }
 
 pub fn decompress(data: &[u8]) -> Result<Vec<u8>, AppError> {
-    let mut decoder = ZlibDecoder::new(data);
     let mut decompressed = Vec::new();
-    decoder.read_to_end(&mut decompressed).map_err(AppError::Decompression)?;
-    if decompressed.len() >= 3 && decompressed[0] == 0xEF && decompressed[1] == 0xBB && decompressed[2] == 0xBF {
-        Ok(decompressed[3..].to_vec())
-    } else {
-        Ok(decompressed)
+    LyricReader::new(data).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+fn stream_error(error: AppError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+pub struct HexReader<R> {
+    inner: R,
+    position: usize,
+    digits: usize,
+    pending: Option<u8>,
+}
+
+impl<R: BufRead> HexReader<R> {
+    pub fn new(inner: R) -> Self {
+        HexReader { inner, position: 0, digits: 0, pending: None }
     }
 }
 
-pub fn decrypt_lyrics(encrypted: &str) -> Result<String, AppError> {
-    let encrypted_bytes = hex_string_to_byte_array(encrypted)?;
-    let mut decrypted_data = vec![0; encrypted_bytes.len()];
-    
-    let mut schedule = vec![vec![vec![0u8; 6]; 16]; 3];
-    triple_des_key_setup(QQ_KEY, &mut schedule, DECRYPT); 
-    
-    for (i, chunk) in encrypted_bytes.chunks(8).enumerate() {
-        if chunk.len() == 8 {
-            let mut temp = [0u8; 8];
-            triple_des_crypt(chunk, &mut temp, &schedule);
-            
-            let start = i * 8;
-            let end = start + 8;
-            if end <= decrypted_data.len() {
-                decrypted_data[start..end].copy_from_slice(&temp);
+impl<R: BufRead> Read for HexReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written == 0 && !out.is_empty() {
+            let input = self.inner.fill_buf()?;
+            if input.is_empty() {
+                if self.pending.is_some() {
+                    return Err(stream_error(AppError::OddHexLength(self.digits)));
+                }
+                break;
             }
+
+            let mut consumed = 0;
+            for &byte in input {
+                if written == out.len() {
+                    break;
+                }
+                consumed += 1;
+                if byte.is_ascii_whitespace() {
+                    continue;
+                }
+
+                let position = self.position + consumed - 1;
+                let digit = (byte as char).to_digit(16)
+                    .ok_or_else(|| stream_error(AppError::InvalidHex { position, found: byte as char }))? as u8;
+                self.digits += 1;
+                match self.pending.take() {
+                    Some(high) => {
+                        out[written] = (high << 4) | digit;
+                        written += 1;
+                    },
+                    None => self.pending = Some(digit),
+                }
+            }
+            self.position += consumed;
+            self.inner.consume(consumed);
         }
+        Ok(written)
     }
-    
-    let decompressed = decompress(&decrypted_data)?;
-    String::from_utf8(decompressed).map_err(AppError::Utf8)
-}
\ No newline at end of file
+}
+
+pub struct DecryptReader<R> {
+    inner: R,
+    schedule: &'static TripleDesSchedule,
+    block: [u8; 8],
+    block_pos: usize,
+    block_len: usize,
+    total: usize,
+}
+
+impl<R: Read> DecryptReader<R> {
+    pub fn new(inner: R) -> Self {
+        DecryptReader { inner, schedule: &DECRYPT_SCHEDULE, block: [0; 8], block_pos: 0, block_len: 0, total: 0 }
+    }
+
+    // 读满整数个 8 字节块后原地解密，读到末尾仍不对齐则报错
+    fn fill(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+        loop {
+            let n = self.inner.read(&mut buf[filled..])?;
+            filled += n;
+            if n == 0 || (filled > 0 && filled.is_multiple_of(8)) {
+                break;
+            }
+        }
+        self.total += filled;
+        if !filled.is_multiple_of(8) {
+            return Err(stream_error(AppError::UnalignedCiphertext(self.total)));
+        }
+
+        for chunk in buf[..filled].chunks_exact_mut(8) {
+            let mut input = [0u8; 8];
+            input.copy_from_slice(chunk);
+            triple_des_crypt(&input, chunk, self.schedule);
+        }
+        Ok(filled)
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.block_pos == self.block_len && out.len() >= 8 {
+            let whole_blocks = out.len() / 8 * 8;
+            return self.fill(&mut out[..whole_blocks]);
+        }
+
+        if self.block_pos == self.block_len {
+            let mut block = [0u8; 8];
+            self.block_len = self.fill(&mut block)?;
+            self.block = block;
+            self.block_pos = 0;
+        }
+
+        let n = out.len().min(self.block_len - self.block_pos);
+        out[..n].copy_from_slice(&self.block[self.block_pos..self.block_pos + n]);
+        self.block_pos += n;
+        Ok(n)
+    }
+}
+
+pub struct Qmc1Reader<R> {
+    inner: R,
+    position: usize,
+}
+
+impl<R: Read> Qmc1Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Qmc1Reader { inner, position: 0 }
+    }
+}
+
+impl<R: Read> Read for Qmc1Reader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(out)?;
+        qmc1_xor(&mut out[..n], self.position);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+// flate2 的 ZlibDecoder 遇到截断的数据流会直接当作正常结束，这里自己驱动 Decompress 以便报错
+pub struct InflateReader<R> {
+    inner: R,
+    decoder: Decompress,
+    finished: bool,
+}
+
+impl<R: BufRead> InflateReader<R> {
+    pub fn new(inner: R) -> Self {
+        InflateReader { inner, decoder: Decompress::new(true), finished: false }
+    }
+}
+
+impl<R: BufRead> Read for InflateReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while !self.finished && !out.is_empty() {
+            let input = self.inner.fill_buf()?;
+            let eof = input.is_empty();
+            let (before_in, before_out) = (self.decoder.total_in(), self.decoder.total_out());
+            let status = self.decoder.decompress(input, out, FlushDecompress::None).map_err(|e| {
+                let source = io::Error::new(io::ErrorKind::InvalidData, e);
+                stream_error(AppError::Decompression { offset: self.decoder.total_in(), source })
+            })?;
+            let consumed = (self.decoder.total_in() - before_in) as usize;
+            let read = (self.decoder.total_out() - before_out) as usize;
+            self.inner.consume(consumed);
+
+            self.finished = status == Status::StreamEnd;
+            if read > 0 || self.finished {
+                return Ok(read);
+            }
+            if eof && consumed == 0 {
+                let source = io::Error::new(io::ErrorKind::UnexpectedEof, "zlib 数据流不完整");
+                return Err(stream_error(AppError::Decompression { offset: self.decoder.total_in(), source }));
+            }
+        }
+        Ok(0)
+    }
+}
+
+pub struct LyricReader<R> {
+    inner: BufReader<InflateReader<BufReader<R>>>,
+    bom_checked: bool,
+}
+
+impl<R: Read> LyricReader<R> {
+    pub fn new(compressed: R) -> Self {
+        LyricReader { inner: BufReader::new(InflateReader::new(BufReader::new(compressed))), bom_checked: false }
+    }
+}
+
+impl<R: Read> Read for LyricReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if !self.bom_checked {
+            self.bom_checked = true;
+            if self.inner.fill_buf()?.starts_with(&[0xEF, 0xBB, 0xBF]) {
+                self.inner.consume(3);
+            }
+        }
+        self.inner.read(out)
+    }
+}
+
+pub fn hex_lyrics_reader<R: Read>(hex: R) -> LyricReader<DecryptReader<HexReader<BufReader<R>>>> {
+    LyricReader::new(DecryptReader::new(HexReader::new(BufReader::new(hex))))
+}
+
+pub fn binary_lyrics_reader<R: Read>(encrypted: R) -> LyricReader<DecryptReader<R>> {
+    LyricReader::new(DecryptReader::new(encrypted))
+}
+
+pub fn local_lyrics_reader<R: Read>(data: R) -> Result<LyricReader<DecryptReader<Qmc1Reader<R>>>, AppError> {
+    let mut reader = Qmc1Reader::new(data);
+    io::copy(&mut (&mut reader).take(LOCAL_QRC_HEADER_LEN as u64), &mut io::sink())?;
+    Ok(binary_lyrics_reader(reader))
+}
+
+fn qmc1_xor(data: &mut [u8], start: usize) {
+    for (i, byte) in data.iter_mut().enumerate() {
+        let i = start + i;
+        let offset = if i > 0x7FFF { i % 0x7FFF } else { i };
+        *byte ^= QMC1_KEY[offset & 0x7F];
+    }
+}
+
+pub fn qmc1_decrypt(data: &mut [u8]) {
+    qmc1_xor(data, 0);
+}
+
+pub fn decrypt_local_lyrics(data: &[u8]) -> Result<String, AppError> {
+    read_lyrics(local_lyrics_reader(data)?)
+}
+
+pub fn is_local_lyrics(len: u64) -> bool {
+    len > LOCAL_QRC_HEADER_LEN as u64 && !len.is_multiple_of(8) && (len - LOCAL_QRC_HEADER_LEN as u64).is_multiple_of(8)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LyricsFileKind {
+    Hex,
+    Binary,
+    Local,
+}
+
+impl LyricsFileKind {
+    // 扫描完整个输入再判断，结束后回到原来的位置
+    pub fn detect<R: Read + Seek>(input: &mut R) -> io::Result<LyricsFileKind> {
+        let start = input.stream_position()?;
+        let mut buf = [0u8; 8192];
+        let mut len = 0u64;
+        let mut has_digit = false;
+        let mut is_hex = true;
+        loop {
+            let n = match input.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            len += n as u64;
+            if is_hex {
+                has_digit |= buf[..n].iter().any(u8::is_ascii_hexdigit);
+                is_hex = buf[..n].iter().all(|b| b.is_ascii_hexdigit() || b.is_ascii_whitespace());
+            }
+        }
+        input.seek(SeekFrom::Start(start))?;
+
+        Ok(if is_hex && has_digit {
+            LyricsFileKind::Hex
+        } else if is_local_lyrics(len) {
+            LyricsFileKind::Local
+        } else {
+            LyricsFileKind::Binary
+        })
+    }
+}
+
+pub fn lyrics_file_reader<'a, R: Read + Seek + 'a>(mut input: R, kind: Option<LyricsFileKind>) -> Result<Box<dyn Read + 'a>, AppError> {
+    let kind = match kind {
+        Some(kind) => kind,
+        None => LyricsFileKind::detect(&mut input)?,
+    };
+    Ok(match kind {
+        LyricsFileKind::Hex => Box::new(hex_lyrics_reader(input)),
+        LyricsFileKind::Binary => Box::new(binary_lyrics_reader(input)),
+        LyricsFileKind::Local => Box::new(local_lyrics_reader(input)?),
+    })
+}
+
+pub fn decrypt_lyrics_file(data: &[u8]) -> Result<String, AppError> {
+    read_lyrics(lyrics_file_reader(io::Cursor::new(data), None)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以下密文由改写前的逐位实现核对过，用来固定查表实现的输出
+    const KAT_PLAIN: &str = "[ti:晴天]\n[0,1000]故(0,500)事(500,500)\n";
+    const KAT_HEX: &str = "CF9CD53258220A79AE8719DE6CC8E4BC02941013874F2022D092F43DEAEDD609C2AD31C3CE69819E47C725BD67053399";
+    const KAT_LOCAL: &str = "C31B8489DC28B41394A064AD038E3B58E154EC8D180ACF12107683BE04AB67898C6756ED025E02BB3522184632EF56D3AA47979258DFDB9EDDC36E";
+    const KAT_BLOCKS: [([u8; 8], &str, &str); 3] = [
+        ([0x00; 8], "A27B02AA779BF226", "D4F80AA34CA0B59C"),
+        ([0xff; 8], "4B789C44381642A3", "5B0DA514D2289913"),
+        (*b"QQMusic!", "1FC932A0F103F3C1", "1CAB122D6DFACA0B"),
+    ];
+
+    #[test]
+    fn detects_lyrics_file_kind() {
+        let ciphertext = hex_string_to_byte_array(KAT_HEX).unwrap();
+        let local = hex_string_to_byte_array(KAT_LOCAL).unwrap();
+        let wrapped_hex = KAT_HEX.as_bytes().chunks(16).flat_map(|c| [c, b"\r\n"]).flatten().copied().collect::<Vec<u8>>();
+
+        for (data, kind) in [(KAT_HEX.as_bytes(), LyricsFileKind::Hex), (&wrapped_hex, LyricsFileKind::Hex), (&ciphertext, LyricsFileKind::Binary), (&local, LyricsFileKind::Local)] {
+            let mut input = io::Cursor::new(data);
+            assert_eq!(LyricsFileKind::detect(&mut input).unwrap(), kind);
+            assert_eq!(input.position(), 0);
+            assert_eq!(decrypt_lyrics_file(data).unwrap(), KAT_PLAIN);
+        }
+    }
+
+    #[test]
+    fn detects_non_hex_after_first_buffer() {
+        let mut data = "0".repeat(20_000).into_bytes();
+        data.extend_from_slice(&[0xff; 4]);
+        assert_eq!(LyricsFileKind::detect(&mut io::Cursor::new(&data)).unwrap(), LyricsFileKind::Binary);
+    }
+
+    #[test]
+    fn triple_des_known_answers() {
+        for (block, encrypted, decrypted) in KAT_BLOCKS {
+            let mut output = [0u8; 8];
+            triple_des_crypt(&block, &mut output, &ENCRYPT_SCHEDULE);
+            assert_eq!(byte_array_to_hex_string(&output), encrypted);
+            triple_des_crypt(&block, &mut output, &DECRYPT_SCHEDULE);
+            assert_eq!(byte_array_to_hex_string(&output), decrypted);
+        }
+    }
+
+    #[test]
+    fn decrypts_known_hex() {
+        assert_eq!(decrypt_lyrics(KAT_HEX).unwrap(), KAT_PLAIN);
+    }
+
+    #[test]
+    fn decrypts_known_binary() {
+        let ciphertext = hex_string_to_byte_array(KAT_HEX).unwrap();
+        assert_eq!(decrypt_lyrics_bytes(&ciphertext).unwrap(), KAT_PLAIN);
+    }
+
+    #[test]
+    fn decrypts_known_local_cache() {
+        let local = hex_string_to_byte_array(KAT_LOCAL).unwrap();
+        assert!(is_local_lyrics(local.len() as u64));
+        assert_eq!(decrypt_local_lyrics(&local).unwrap(), KAT_PLAIN);
+    }
+
+    fn samples() -> Vec<String> {
+        let long = (0..400).map(|i| format!("[{},1000]第{}行歌词(1000,500)lyric(1500,500)\n", i * 1000, i)).collect();
+        vec![String::new(), "1234567".to_string(), "12345678".to_string(), "[ti:晴天]".to_string(), long]
+    }
+
+    #[test]
+    fn round_trips_hex() {
+        for plain in samples() {
+            let encrypted = encrypt_lyrics(&plain).unwrap();
+            assert_eq!(decrypt_lyrics(&encrypted).unwrap(), plain);
+            assert_eq!(decrypt_lyrics(&encrypted.to_lowercase()).unwrap(), plain);
+        }
+    }
+
+    #[test]
+    fn round_trips_bytes() {
+        for plain in samples() {
+            let encrypted = hex_string_to_byte_array(&encrypt_lyrics(&plain).unwrap()).unwrap();
+            assert_eq!(encrypted.len() % 8, 0);
+            assert_eq!(decrypt_lyrics_bytes(&encrypted).unwrap(), plain);
+        }
+    }
+}
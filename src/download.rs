@@ -0,0 +1,160 @@
+use reqwest::Client;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::align;
+use crate::api::{self, LyricResult, Song};
+use crate::convert::{self, align_qrc_tracks, parse_optional};
+use crate::lyric::LyricMetadata;
+use crate::{AppError, Result, log_warn, lrc, qrc, utils};
+
+pub use crate::convert::{ConvertOptions, LyricFormat};
+
+#[derive(Debug, Clone)]
+pub struct SaveOptions {
+    pub output_dir: PathBuf,
+    pub overwrite: bool,
+    pub keep_qrc: bool,
+    pub convert: ConvertOptions,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        SaveOptions { output_dir: PathBuf::from("."), overwrite: true, keep_qrc: false, convert: ConvertOptions::default() }
+    }
+}
+
+impl SaveOptions {
+    pub fn path_for(&self, file_name: &str) -> PathBuf {
+        self.output_dir.join(file_name)
+    }
+
+    fn prepare(&self, path: &Path) -> Result<()> {
+        if !self.overwrite && path.exists() {
+            return Err(AppError::FileExists(path.display().to_string()));
+        }
+        if !self.output_dir.as_os_str().is_empty() {
+            fs::create_dir_all(&self.output_dir)?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+        self.prepare(path)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+}
+
+pub fn save_lyrics(base_filename: &str, ext: &str, lyric_content: &str, trans_content: Option<&str>, roma_content: Option<&str>, options: &SaveOptions) -> Result<Vec<PathBuf>> {
+    let mut saved = Vec::new();
+
+    let filename = options.path_for(&format!("{}.{}", base_filename, ext));
+    options.write(&filename, lyric_content)?;
+    saved.push(filename);
+
+    if let Some(trans) = trans_content.filter(|t| !t.is_empty()) {
+        let trans_filename = options.path_for(&format!("{}_trans.lrc", base_filename));
+        options.write(&trans_filename, trans)?;
+        saved.push(trans_filename);
+    }
+
+    if let Some(roma) = roma_content.filter(|r| !r.is_empty()) {
+        let roma_filename = options.path_for(&format!("{}_roma.qrc", base_filename));
+        options.write(&roma_filename, roma)?;
+        saved.push(roma_filename);
+    }
+
+    Ok(saved)
+}
+
+pub async fn download_lyrics(client: &Client, song: &Song, format: LyricFormat, options: &SaveOptions) -> Result<(Option<Vec<PathBuf>>, String)> {
+    download_lyrics_as(client, song, &utils::create_safe_filename(song), format, options).await
+}
+
+pub fn song_metadata(song: &Song) -> LyricMetadata {
+    let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+    let mut metadata = LyricMetadata {
+        title: non_empty(&song.name),
+        artist: non_empty(&song.artists()),
+        album: non_empty(&song.album.name),
+        ..LyricMetadata::default()
+    };
+    if let Some(length) = song.duration_text() {
+        metadata.set_tag("length", &length);
+    }
+    metadata
+}
+
+pub async fn download_lyrics_as(client: &Client, song: &Song, base_filename: &str, format: LyricFormat, options: &SaveOptions) -> Result<(Option<Vec<PathBuf>>, String)> {
+    let metadata = song_metadata(song);
+
+    match format {
+        LyricFormat::Lrc => {
+            let (lyrics, raw_response) = api::get_lyric(client, &song.mid).await?;
+            let Some((lyric, trans)) = fetch_lrc_or_qrc_fallback(client, song, lyrics).await? else {
+                return Ok((None, raw_response));
+            };
+
+            if options.convert.merge_tracks {
+                let original = lrc::parse(&lyric)?;
+                let translation = parse_optional(trans.as_deref(), lrc::parse)?;
+                let mut aligned = align::align(&original, translation.as_ref(), None, align::DEFAULT_TOLERANCE_MS);
+                aligned.metadata.merge_missing(&metadata);
+                let saved = save_lyrics(base_filename, "lrc", &lrc::write_aligned(&aligned), None, None, options)?;
+                return Ok((Some(saved), raw_response));
+            }
+
+            let saved = save_lyrics(base_filename, "lrc", &lrc::fill_tags(&lyric, &metadata), trans.as_deref(), None, options)?;
+            Ok((Some(saved), raw_response))
+        },
+        _ => {
+            let (lyrics, raw_response) = api::get_lyrics_by_id(client, &song.id.to_string()).await?;
+            let Some(lyrics) = lyrics else { return Ok((None, raw_response)) };
+            for error in &lyrics.errors {
+                log_warn!("{}: {}", song.name, error);
+            }
+            let mut saved = Vec::new();
+
+            if format == LyricFormat::Qrc || options.keep_qrc {
+                saved = if options.convert.merge_tracks {
+                    let merged = qrc::write_aligned(&align_qrc_tracks(&lyrics)?);
+                    save_lyrics(base_filename, "qrc", &merged, None, None, options)?
+                } else {
+                    save_lyrics(base_filename, "qrc", &lyrics.lyrics, Some(&lyrics.trans), Some(&lyrics.roma), options)?
+                };
+            }
+
+            if format != LyricFormat::Qrc {
+                // 先在内存中完成转换，解析失败时不会留下空文件
+                let mut rendered = Vec::new();
+                convert::write_converted(&mut rendered, &lyrics, &metadata, format, &options.convert)?;
+                let path = options.path_for(&format!("{}.{}", base_filename, format.extension()));
+                options.write(&path, rendered)?;
+                saved.push(path);
+            }
+            Ok((Some(saved), raw_response))
+        },
+    }
+}
+
+async fn fetch_lrc_or_qrc_fallback(client: &Client, song: &Song, lyrics: Option<LyricResult>) -> Result<Option<(String, Option<String>)>> {
+    if let Some(lyrics) = &lyrics && lrc::has_usable_timing(&lyrics.lyric) {
+        return Ok(Some((lyrics.lyric.clone(), lyrics.trans.clone())));
+    }
+
+    let fallback = match api::get_lyrics_by_id(client, &song.id.to_string()).await {
+        Ok((Some(qrc_lyrics), _)) if !qrc_lyrics.lyrics.is_empty() => {
+            lrc::convert_qrc_to_lrc(&qrc_lyrics.lyrics).map(|lyric| (lyric, Some(qrc_lyrics.trans)))
+        },
+        Ok(_) => Err(AppError::LyricNotFound),
+        Err(e) => Err(e),
+    };
+
+    match (fallback, lyrics) {
+        (Ok(converted), _) => Ok(Some(converted)),
+        (Err(_), Some(lyrics)) => Ok(Some((lyrics.lyric, lyrics.trans))),
+        (Err(AppError::LyricNotFound), None) => Ok(None),
+        (Err(e), None) => Err(e),
+    }
+}
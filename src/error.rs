@@ -0,0 +1,84 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("网络请求错误: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error("JSON 解析错误: {0}")]
+    JsonParse(#[from] serde_json::Error),
+    #[error("XML 解析错误: {0}")]
+    XmlParse(#[from] quick_xml::Error),
+    #[error("Base64 解码错误: {0}")]
+    Base64Decode(#[from] base64::DecodeError),
+    #[error("UTF-8 转换错误: {0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
+    #[error("IO 错误: {0}")]
+    Io(#[source] std::io::Error),
+    #[error("解压缩错误 (偏移 {offset} 字节): {source}")]
+    Decompression { offset: u64, #[source] source: std::io::Error },
+    #[error("时间转换错误: {0}")]
+    SystemTime(#[from] std::time::SystemTimeError),
+    #[error("无效的十六进制字符 {found:?} (第 {position} 个字符)")]
+    InvalidHex { position: usize, found: char },
+    #[error("十六进制字符串长度为奇数: {0}")]
+    OddHexLength(usize),
+    #[error("密文长度 {0} 字节不是 8 字节的整数倍")]
+    UnalignedCiphertext(usize),
+    #[error("歌词不是有效的 UTF-8 (第 {position} 字节): {source}")]
+    InvalidUtf8 { position: usize, #[source] source: std::string::FromUtf8Error },
+    #[error("解密 {field} 失败: {source}")]
+    LyricDecrypt { field: String, #[source] source: Box<AppError> },
+    #[error("API 返回错误: {0}")]
+    ApiError(String),
+    #[error("未找到歌曲")]
+    SongNotFound,
+    #[error("未找到专辑")]
+    AlbumNotFound,
+    #[error("未找到歌单")]
+    PlaylistNotFound,
+    #[error("未找到歌手")]
+    SingerNotFound,
+    #[error("未找到歌词")]
+    LyricNotFound,
+    #[error("无效的用户输入")]
+    InvalidInput,
+    #[error("文件已存在: {0}")]
+    FileExists(String),
+    #[error("批量下载未全部成功: 成功 {succeeded}, 未找到 {not_found}, 失败 {failed}")]
+    BatchIncomplete { succeeded: usize, not_found: usize, failed: usize },
+    #[error("歌词解析错误 (第 {line} 行, 第 {column} 列): {message}")]
+    LyricParse { line: usize, column: usize, message: String },
+}
+
+impl AppError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            AppError::InvalidInput => 2,
+            AppError::Network(_) => 3,
+            AppError::ApiError(_) => 4,
+            AppError::JsonParse(_) | AppError::XmlParse(_) | AppError::Base64Decode(_) |
+            AppError::Utf8(_) | AppError::Decompression { .. } | AppError::InvalidHex { .. } |
+            AppError::OddHexLength(_) | AppError::UnalignedCiphertext(_) | AppError::InvalidUtf8 { .. } |
+            AppError::LyricParse { .. } => 5,
+            AppError::LyricDecrypt { source, .. } => source.exit_code(),
+            AppError::Io(_) | AppError::FileExists(_) => 6,
+            AppError::SongNotFound | AppError::AlbumNotFound | AppError::PlaylistNotFound |
+            AppError::SingerNotFound => 7,
+            AppError::LyricNotFound => 8,
+            AppError::BatchIncomplete { .. } => 9,
+            AppError::SystemTime(_) => 1,
+        }
+    }
+}
+
+// 流式读取器通过 io::Error 传递的 AppError 在这里还原
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        match error.downcast::<AppError>() {
+            Ok(inner) => inner,
+            Err(error) => AppError::Io(error),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, AppError>;
@@ -0,0 +1,537 @@
+use reqwest::Client;
+use std::io::{Write, stdin, stdout};
+
+use qrcdownloader::{Result, LyricFormat, SaveOptions, download_lyrics, log_info, log_success, log_error, log_warn};
+use qrcdownloader::api::{
+    AlbumDetail, Playlist, SearchPage, SearchQuery, SearchType, SingerSummary, Song,
+    search, get_song, get_album_songs, get_all_singer_songs, get_playlist, get_singer_songs, parse_playlist_id,
+};
+use qrcdownloader::batch::{DEFAULT_PARALLELISM, dedup_songs, run_album, run_playlist, run_singer};
+
+use crate::cli::{print_batch_progress, print_batch_summary};
+
+pub async fn run(client: &Client) -> Result<()> {
+    loop {
+        print_menu("=======================\n  QQ 音乐歌词下载器\n=======================",
+                  &["1. 搜索歌曲并获取歌词", "2. 通过歌曲 ID/MID 获取歌词", "3. 搜索专辑并下载整张专辑的歌词", "4. 通过歌单 ID/链接下载歌单歌词", "5. 搜索歌手并下载歌手的歌词", "q. 退出"]);
+        
+        match prompt_and_get_input("请选择操作 (1-5/q):")?.trim() {
+            "1" => handle_search_mode(client).await.unwrap_or_else(|e| log_error!("处理搜索时出错: {}", e)),
+            "2" => handle_id_mode(client).await.unwrap_or_else(|e| log_error!("处理ID/MID输入时出错: {}", e)),
+            "3" => handle_album_mode(client).await.unwrap_or_else(|e| log_error!("处理专辑时出错: {}", e)),
+            "4" => handle_playlist_mode(client).await.unwrap_or_else(|e| log_error!("处理歌单时出错: {}", e)),
+            "5" => handle_singer_mode(client).await.unwrap_or_else(|e| log_error!("处理歌手时出错: {}", e)),
+            "q" => break,
+            _ => log_warn!("无效选项，请输入1到5或q"),
+        }
+    }
+    log_info!("正在退出程序...");
+    Ok(())
+}
+
+fn print_menu(title: &str, options: &[&str]) {
+    println!("\n{}", title);
+    for option in options {
+        println!("{}", option);
+    }
+    println!("-----------------------");
+}
+
+fn prompt_and_get_input(prompt_text: &str) -> Result<String> {
+    log_info!("{} ", prompt_text);
+    stdout().flush()?;
+    let mut input = String::new();
+    stdin().read_line(&mut input)?;
+    Ok(input)
+}
+
+async fn handle_search_mode(client: &Client) -> Result<()> {
+    loop {
+        let keyword = prompt_and_get_input("请输入歌曲名称 (输入 'q' 返回上一级):")?.trim().to_string();
+        if keyword == "q" { break; }
+        if keyword.is_empty() {
+            log_warn!("搜索关键词不能为空。");
+            continue;
+        }
+        let mut query = SearchQuery::new(&keyword);
+        loop {
+            log_info!("正在搜索: {} (第 {} 页)", keyword, query.page);
+            match search(client, &query).await {
+                Ok((page, raw_response)) if page.songs.is_empty() => {
+                    log_error!("未找到与 '{}'相关的歌曲。", keyword);
+                    print_raw_response(&raw_response);
+                    break;
+                },
+                Ok((page, _)) => match process_song_selection(client, &page).await? {
+                    Selection::Downloaded => return Ok(()),
+                    Selection::Back => break,
+                    Selection::Page(number) => query.page = number,
+                },
+                Err(e) => {
+                    log_error!("搜索歌曲时出错: {}", e);
+                    break;
+                },
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn handle_id_mode(client: &Client) -> Result<()> {
+    loop {
+        let id = prompt_and_get_input("请输入歌曲 ID 或 MID (输入 'q' 返回上一级):")?.trim().to_string();
+        if id == "q" { break; }
+        if id.is_empty() {
+            log_warn!("ID/MID 不能为空。");
+            continue;
+        }
+        log_info!("正在获取歌曲信息: {}", id);
+        match get_song(client, &id).await {
+            Ok((Some(song), _)) => {
+                print_song_info(&song);
+                if process_lyric_format_choice(client, &song).await? { break }
+            },
+            Ok((None, json_str)) => {
+                log_warn!("未找到 ID/MID 为 '{}' 的歌曲信息。", id);
+                print_raw_response(&json_str);
+            },
+            Err(e) => log_error!("获取歌曲信息时出错: {}", e),
+        }
+    }
+    Ok(())
+}
+
+async fn handle_album_mode(client: &Client) -> Result<()> {
+    loop {
+        let keyword = prompt_and_get_input("请输入专辑名称 (输入 'q' 返回上一级):")?.trim().to_string();
+        if keyword == "q" { break; }
+        if keyword.is_empty() {
+            log_warn!("搜索关键词不能为空。");
+            continue;
+        }
+        let mut query = SearchQuery { search_type: SearchType::Album, ..SearchQuery::new(&keyword) };
+        loop {
+            log_info!("正在搜索专辑: {} (第 {} 页)", keyword, query.page);
+            match search(client, &query).await {
+                Ok((page, raw_response)) if page.albums.is_empty() => {
+                    log_error!("未找到与 '{}'相关的专辑。", keyword);
+                    print_raw_response(&raw_response);
+                    break;
+                },
+                Ok((page, _)) => match process_album_selection(client, &page).await? {
+                    Selection::Downloaded => return Ok(()),
+                    Selection::Back => break,
+                    Selection::Page(number) => query.page = number,
+                },
+                Err(e) => {
+                    log_error!("搜索专辑时出错: {}", e);
+                    break;
+                },
+            }
+        }
+    }
+    Ok(())
+}
+
+enum Selection {
+    Downloaded,
+    Back,
+    Page(u32),
+}
+
+fn selection_prompt(page: &SearchPage, count: usize, item: &str) -> String {
+    let mut prompt = format!("请选择{}序号 (1-{}", item, count);
+    if page.has_next() { prompt.push_str(", 'n' 下一页"); }
+    if page.has_previous() { prompt.push_str(", 'p' 上一页"); }
+    prompt.push_str(", 输入 'q' 返回):");
+    prompt
+}
+
+fn page_command(selection: &str, page: &SearchPage) -> Option<Selection> {
+    match selection {
+        "q" => Some(Selection::Back),
+        "n" if page.has_next() => Some(Selection::Page(page.page + 1)),
+        "p" if page.has_previous() => Some(Selection::Page(page.page - 1)),
+        _ => None,
+    }
+}
+
+async fn process_song_selection(client: &Client, page: &SearchPage) -> Result<Selection> {
+    let songs = &page.songs;
+    log_info!("找到以下歌曲 (共 {} 首, 第 {}/{} 页):", page.total, page.page, page.page_count());
+    for (index, song) in songs.iter().enumerate() {
+        println!("{}. {}", index + 1, song_listing(song));
+    }
+    println!("-----------------------");
+
+    let prompt = selection_prompt(page, songs.len(), "歌曲");
+    loop {
+        let selection = prompt_and_get_input(&prompt)?.trim().to_string();
+        if let Some(command) = page_command(&selection, page) {
+            return Ok(command);
+        }
+
+        match selection.parse::<usize>() {
+            Ok(num) if (1..=songs.len()).contains(&num) => {
+                let selected_song = &songs[num - 1];
+                print_song_info(selected_song);
+                if process_lyric_format_choice(client, selected_song).await? {
+                    return Ok(Selection::Downloaded);
+                }
+                return Ok(Selection::Back);
+            },
+            _ => log_warn!("请输入1到{}之间的有效序号。", songs.len()),
+        }
+    }
+}
+
+async fn process_album_selection(client: &Client, page: &SearchPage) -> Result<Selection> {
+    let albums = &page.albums;
+    log_info!("找到以下专辑 (共 {} 张, 第 {}/{} 页):", page.total, page.page, page.page_count());
+    for (index, album) in albums.iter().enumerate() {
+        println!("{}. {} - {} ({} 首, {})", index + 1, album.name, album.singer_name, album.song_count, album.public_time);
+    }
+    println!("-----------------------");
+
+    let prompt = selection_prompt(page, albums.len(), "专辑");
+    loop {
+        let selection = prompt_and_get_input(&prompt)?.trim().to_string();
+        if let Some(command) = page_command(&selection, page) {
+            return Ok(command);
+        }
+
+        match selection.parse::<usize>() {
+            Ok(num) if (1..=albums.len()).contains(&num) => {
+                let album = &albums[num - 1];
+                log_info!("正在获取专辑曲目: {}", album.name);
+                return match get_album_songs(client, &album.mid).await {
+                    Ok((Some(detail), _)) => {
+                        if process_album_download(client, &detail).await? {
+                            Ok(Selection::Downloaded)
+                        } else {
+                            Ok(Selection::Back)
+                        }
+                    },
+                    Ok((None, raw_response)) => {
+                        log_warn!("未找到专辑 '{}' 的曲目。", album.name);
+                        print_raw_response(&raw_response);
+                        Ok(Selection::Back)
+                    },
+                    Err(e) => {
+                        log_error!("获取专辑曲目失败: {}", e);
+                        Ok(Selection::Back)
+                    },
+                };
+            },
+            _ => log_warn!("请输入1到{}之间的有效序号。", albums.len()),
+        }
+    }
+}
+
+async fn process_album_download(client: &Client, album: &AlbumDetail) -> Result<bool> {
+    log_info!("\n--- 专辑: {} - {} ---", album.singer_names(), album.name);
+    for track in &album.tracks {
+        println!("{:02}. {}", track.number, song_listing(&track.song));
+    }
+    println!("----------------");
+
+    let mut options = SaveOptions::default();
+    let Some(format) = prompt_lyric_format(&mut options)? else { return Ok(false) };
+
+    log_info!("正在下载 {} 首歌曲的 {} 歌词...", album.tracks.len(), format.label());
+    let report = run_album(client, album, format, &options, DEFAULT_PARALLELISM, print_batch_progress).await;
+    print_batch_summary(&report);
+    Ok(true)
+}
+
+async fn handle_singer_mode(client: &Client) -> Result<()> {
+    loop {
+        let keyword = prompt_and_get_input("请输入歌手名称 (输入 'q' 返回上一级):")?.trim().to_string();
+        if keyword == "q" { break; }
+        if keyword.is_empty() {
+            log_warn!("搜索关键词不能为空。");
+            continue;
+        }
+        let mut query = SearchQuery { search_type: SearchType::Singer, ..SearchQuery::new(&keyword) };
+        loop {
+            log_info!("正在搜索歌手: {} (第 {} 页)", keyword, query.page);
+            match search(client, &query).await {
+                Ok((page, raw_response)) if page.singers.is_empty() => {
+                    log_error!("未找到与 '{}'相关的歌手。", keyword);
+                    print_raw_response(&raw_response);
+                    break;
+                },
+                Ok((page, _)) => match process_singer_selection(client, &page).await? {
+                    Selection::Downloaded => return Ok(()),
+                    Selection::Back => break,
+                    Selection::Page(number) => query.page = number,
+                },
+                Err(e) => {
+                    log_error!("搜索歌手时出错: {}", e);
+                    break;
+                },
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn process_singer_selection(client: &Client, page: &SearchPage) -> Result<Selection> {
+    let singers = &page.singers;
+    log_info!("找到以下歌手 (共 {} 位, 第 {}/{} 页):", page.total, page.page, page.page_count());
+    for (index, singer) in singers.iter().enumerate() {
+        println!("{}. {} ({} 首歌曲, {} 张专辑)", index + 1, singer.name, singer.song_count, singer.album_count);
+    }
+    println!("-----------------------");
+
+    let prompt = selection_prompt(page, singers.len(), "歌手");
+    loop {
+        let selection = prompt_and_get_input(&prompt)?.trim().to_string();
+        if let Some(command) = page_command(&selection, page) {
+            return Ok(command);
+        }
+
+        match selection.parse::<usize>() {
+            Ok(num) if (1..=singers.len()).contains(&num) => {
+                return if process_singer_songs(client, &singers[num - 1]).await? {
+                    Ok(Selection::Downloaded)
+                } else {
+                    Ok(Selection::Back)
+                };
+            },
+            _ => log_warn!("请输入1到{}之间的有效序号。", singers.len()),
+        }
+    }
+}
+
+async fn process_singer_songs(client: &Client, singer: &SingerSummary) -> Result<bool> {
+    const PAGE_SIZE: u32 = 30;
+    let mut begin = 0;
+
+    loop {
+        log_info!("正在获取 {} 的歌曲列表...", singer.name);
+        let page = match get_singer_songs(client, &singer.mid, begin, PAGE_SIZE).await {
+            Ok((Some(page), _)) => page,
+            Ok((None, raw_response)) => {
+                log_warn!("未找到歌手 '{}' 的歌曲。", singer.name);
+                print_raw_response(&raw_response);
+                return Ok(false);
+            },
+            Err(e) => {
+                log_error!("获取歌曲列表失败: {}", e);
+                return Ok(false);
+            },
+        };
+
+        let page_count = page.total.div_ceil(PAGE_SIZE).max(1);
+        let has_next = begin + PAGE_SIZE < page.total;
+        let has_previous = begin > 0;
+        log_info!("{} 的歌曲 (共 {} 首, 第 {}/{} 页):", singer.name, page.total, begin / PAGE_SIZE + 1, page_count);
+        for (index, song) in page.songs.iter().enumerate() {
+            println!("{}. {}", index + 1, song_listing(song));
+        }
+        println!("-----------------------");
+
+        let mut prompt = format!("请选择歌曲序号 (1-{}, 'a' 下载全部", page.songs.len());
+        if has_next { prompt.push_str(", 'n' 下一页"); }
+        if has_previous { prompt.push_str(", 'p' 上一页"); }
+        prompt.push_str(", 输入 'q' 返回):");
+
+        loop {
+            let selection = prompt_and_get_input(&prompt)?.trim().to_string();
+            match selection.as_str() {
+                "q" => return Ok(false),
+                "a" => return process_singer_download(client, singer).await,
+                "n" if has_next => {
+                    begin += PAGE_SIZE;
+                    break;
+                },
+                "p" if has_previous => {
+                    begin = begin.saturating_sub(PAGE_SIZE);
+                    break;
+                },
+                _ => {},
+            }
+
+            match selection.parse::<usize>() {
+                Ok(num) if (1..=page.songs.len()).contains(&num) => {
+                    let selected_song = &page.songs[num - 1];
+                    print_song_info(selected_song);
+                    return process_lyric_format_choice(client, selected_song).await;
+                },
+                _ => log_warn!("请输入1到{}之间的有效序号。", page.songs.len()),
+            }
+        }
+    }
+}
+
+async fn process_singer_download(client: &Client, singer: &SingerSummary) -> Result<bool> {
+    log_info!("正在获取 {} 的全部歌曲...", singer.name);
+    let songs = match get_all_singer_songs(client, &singer.mid).await {
+        Ok((Some(songs), _)) => songs,
+        Ok((None, raw_response)) => {
+            log_warn!("未找到歌手 '{}' 的歌曲。", singer.name);
+            print_raw_response(&raw_response);
+            return Ok(false);
+        },
+        Err(e) => {
+            log_error!("获取歌曲列表失败: {}", e);
+            return Ok(false);
+        },
+    };
+    let total = songs.len();
+    let songs = dedup_songs(songs);
+    log_info!("共 {} 首歌曲，去重后 {} 首", total, songs.len());
+
+    let mut options = SaveOptions::default();
+    let Some(format) = prompt_lyric_format(&mut options)? else { return Ok(false) };
+
+    let report = run_singer(client, &singer.name, songs, format, &options, DEFAULT_PARALLELISM, print_batch_progress).await;
+    print_batch_summary(&report);
+    Ok(true)
+}
+
+async fn handle_playlist_mode(client: &Client) -> Result<()> {
+    loop {
+        let input = prompt_and_get_input("请输入歌单 ID 或分享链接 (输入 'q' 返回上一级):")?.trim().to_string();
+        if input == "q" { break; }
+        if input.is_empty() {
+            log_warn!("歌单 ID 不能为空。");
+            continue;
+        }
+        let Some(disstid) = parse_playlist_id(&input) else {
+            log_warn!("无法从 '{}' 中识别歌单 ID。", input);
+            continue;
+        };
+        log_info!("正在获取歌单: {}", disstid);
+        match get_playlist(client, disstid).await {
+            Ok((Some(playlist), _)) => if process_playlist_download(client, &playlist).await? { break },
+            Ok((None, raw_response)) => {
+                log_warn!("未找到 ID 为 '{}' 的歌单。", disstid);
+                print_raw_response(&raw_response);
+            },
+            Err(e) => log_error!("获取歌单时出错: {}", e),
+        }
+    }
+    Ok(())
+}
+
+async fn process_playlist_download(client: &Client, playlist: &Playlist) -> Result<bool> {
+    log_info!("\n--- 歌单: {} (创建者: {}) ---", playlist.title, playlist.creator);
+    for (index, song) in playlist.songs.iter().enumerate() {
+        println!("{}. {}", index + 1, song_listing(song));
+    }
+    println!("----------------");
+
+    let mut options = SaveOptions::default();
+    let Some(format) = prompt_lyric_format(&mut options)? else { return Ok(false) };
+
+    log_info!("正在下载 {} 首歌曲的 {} 歌词...", playlist.songs.len(), format.label());
+    let report = run_playlist(client, playlist, format, &options, DEFAULT_PARALLELISM, print_batch_progress).await;
+    print_batch_summary(&report);
+    Ok(true)
+}
+
+fn prompt_lyric_format(options: &mut SaveOptions) -> Result<Option<LyricFormat>> {
+    loop {
+        print_menu("\n选择歌词格式:", &[
+            "1. LRC (逐行)", 
+            "2. QRC (逐字)", 
+            "3. ASS 字幕 (从 QRC 转换)",
+            "4. 增强型 LRC (逐字, 从 QRC 转换)",
+            "5. SRT 字幕 (从 QRC 转换)",
+            "6. WebVTT 字幕 (从 QRC 转换)",
+            "7. TTML (Apple Music 格式, 从 QRC 转换)",
+            "q. 返回"
+        ]);
+        let format = match prompt_and_get_input("请输入选择 (1-7/q):")?.trim() {
+            "1" => LyricFormat::Lrc,
+            "2" => LyricFormat::Qrc,
+            "3" => LyricFormat::Ass,
+            "4" => LyricFormat::EnhancedLrc,
+            "5" => LyricFormat::Srt,
+            "6" => LyricFormat::WebVtt,
+            "7" => LyricFormat::Ttml,
+            "q" => return Ok(None),
+            _ => {
+                log_warn!("无效选择。");
+                continue;
+            },
+        };
+
+        if !matches!(format, LyricFormat::Lrc | LyricFormat::Qrc) {
+            options.keep_qrc = prompt_and_get_input("是否同时保存 QRC 原文件? (y/N):")?.trim().eq_ignore_ascii_case("y");
+        }
+        return Ok(Some(format));
+    }
+}
+
+async fn process_lyric_format_choice(client: &Client, song: &Song) -> Result<bool> {
+    let mut options = SaveOptions::default();
+    loop {
+        let Some(format) = prompt_lyric_format(&mut options)? else { return Ok(false) };
+
+        let label = format.label();
+        log_info!("正在获取 {} 歌词...", label);
+        match download_lyrics(client, song, format, &options).await {
+            Ok((Some(saved), _)) => {
+                for path in saved {
+                    log_success!("歌词已保存至: {}", path.display());
+                }
+                return Ok(true);
+            },
+            Ok((None, raw_response)) => {
+                log_warn!("未找到 '{}' 的 {} 歌词。", song.name, label);
+                print_raw_response(&raw_response);
+            },
+            Err(e) => log_error!("获取 {} 歌词失败: {}", label, e),
+        }
+    }
+}
+
+pub fn print_song_info(song: &Song) {
+    log_info!("\n--- 歌曲信息 ---");
+    if song.subtitle.is_empty() {
+        println!("歌曲: {}", song.name);
+    } else {
+        println!("歌曲: {} ({})", song.name, song.subtitle);
+    }
+    println!("艺人: {}", song.artists());
+    if !song.album.name.is_empty() {
+        println!("专辑: {}", song.album.name);
+    }
+    if let Some(duration) = song.duration_text() {
+        println!("时长: {}", duration);
+    }
+    if !song.time_public.is_empty() {
+        println!("发行: {}", song.time_public);
+    }
+    let formats = song.file_formats();
+    if !formats.is_empty() {
+        println!("音质: {}", formats.join(" / "));
+    }
+    if let Some(cover) = song.cover_url() {
+        println!("封面: {}", cover);
+    }
+    println!("ID:   {}", song.id);
+    println!("MID:  {}", song.mid);
+    println!("----------------");
+}
+
+fn song_listing(song: &Song) -> String {
+    let mut listing = format!("{} - {}", song.name, song.artists());
+    if !song.album.name.is_empty() {
+        listing.push_str(&format!(" 《{}》", song.album.name));
+    }
+    if let Some(duration) = song.duration_text() {
+        listing.push_str(&format!(" [{}]", duration));
+    }
+    listing
+}
+
+fn print_raw_response(raw_response: &str) {
+    println!("\n服务器返回的完整内容:");
+    println!("-----------------------");
+    println!("{}", raw_response);
+    println!("-----------------------");
+}
@@ -0,0 +1,26 @@
+pub mod error;
+pub mod utils;
+pub mod decrypto;
+pub mod lyric;
+pub mod qrc;
+pub mod lrc;
+pub mod align;
+pub mod api;
+pub mod ass_converter;
+pub mod ass_style;
+pub mod subtitle;
+pub mod ttml;
+pub mod convert;
+pub mod download;
+pub mod batch;
+
+pub use error::{AppError, Result};
+pub use api::{Song, Singer, LyricResult, QqLyricsResponse, Album, AlbumDetail, AlbumTrack, Playlist, SearchPage, SearchQuery, SearchType, SingerSongPage, SingerSummary, build_client, search, search_song, get_album_songs, get_playlist, parse_playlist_id, get_singer_songs, get_all_singer_songs, get_song, get_lyric, get_lyrics_by_id};
+pub use decrypto::{LyricsFileKind, decrypt_lyrics, decrypt_lyrics_file, decrypt_local_lyrics, encrypt_lyrics, lyrics_file_reader};
+pub use lyric::{Lyric, LyricMetadata, LyricLine, Syllable};
+pub use align::{AlignedLyric, AlignedLine, align};
+pub use ass_converter::convert_qrc_to_ass;
+pub use ass_style::{AssLayout, AssProfile, AssStyle, KaraokeEffect, KaraokeOptions};
+pub use convert::{ConvertOptions, LyricFormat};
+pub use download::{SaveOptions, download_lyrics};
+pub use batch::{BatchEntry, BatchOutcome, BatchProgress, BatchReport, run_album, run_batch, run_batch_with_progress, run_playlist, run_singer};
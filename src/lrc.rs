@@ -0,0 +1,423 @@
+use crate::align::AlignedLyric;
+use crate::lyric::{Lyric, LyricLine, LyricMetadata, Syllable, split_tag};
+use crate::{AppError, Result};
+
+pub fn parse(content: &str) -> Result<Lyric> {
+    let mut lyric = Lyric::default();
+    let mut entries: Vec<(u64, Vec<WordTag>, String)> = Vec::new();
+
+    for (index, raw_line) in content.trim_start_matches('\u{feff}').lines().enumerate() {
+        let line_no = index + 1;
+        let line = raw_line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some((key, value)) = split_tag(line.trim()) {
+            lyric.metadata.set_tag(key, value);
+            continue;
+        }
+
+        let error = |byte_pos: usize, message: String| AppError::LyricParse {
+            line: line_no,
+            column: line[..byte_pos].chars().count() + 1,
+            message,
+        };
+
+        // 遇到第一个不是时间标签的方括号就停止，余下部分都当作歌词文本
+        let mut timestamps = Vec::new();
+        let mut rest = line;
+        while let Some(inner) = rest.strip_prefix('[')
+            && let Some(close) = inner.find(']')
+            && let Some(time) = parse_timestamp(&inner[..close])
+        {
+            timestamps.push(time);
+            rest = &inner[close + 1..];
+        }
+
+        // 没有时间标签的行无法定位，跳过而不是让整首歌词解析失败
+        if timestamps.is_empty() {
+            continue;
+        }
+
+        let text_offset = line.len() - rest.len();
+        let words = parse_word_tags(rest).map_err(|(pos, tag)| error(text_offset + pos, format!("无效的逐字时间标签 '<{}>'", tag)))?;
+        for time in timestamps {
+            entries.push((time, words.clone(), rest.to_string()));
+        }
+    }
+
+    entries.sort_by_key(|(time, _, _)| *time);
+
+    for (i, (start, words, text)) in entries.iter().enumerate() {
+        if text.trim().is_empty() {
+            continue;
+        }
+        let next_start = entries.get(i + 1).map(|(time, _, _)| *time);
+        lyric.lines.push(build_line(*start, next_start, words, text));
+    }
+
+    apply_offset(&mut lyric);
+    Ok(lyric)
+}
+
+fn build_line(start: u64, next_start: Option<u64>, words: &[WordTag], text: &str) -> LyricLine {
+    if words.is_empty() {
+        let duration = next_start.map_or(0, |next| next.saturating_sub(start));
+        return LyricLine { start, duration, syllables: vec![Syllable { text: text.to_string(), start, duration }] };
+    }
+
+    // 第一个逐字标签之前的文本从行时间开始
+    let time = |word: &WordTag| word.time.unwrap_or(start);
+    let mut syllables = Vec::new();
+    for (j, word) in words.iter().enumerate() {
+        if word.text.is_empty() {
+            continue;
+        }
+        let end = words.get(j + 1).map(time).or(next_start).unwrap_or(time(word));
+        syllables.push(Syllable { text: word.text.clone(), start: time(word), duration: end.saturating_sub(time(word)) });
+    }
+
+    let end = syllables.last().map_or(start, Syllable::end).max(start);
+    let end = match words.last() {
+        Some(last) if last.text.is_empty() => end.max(time(last)),
+        _ => end,
+    };
+    LyricLine { start, duration: end - start, syllables }
+}
+
+fn apply_offset(lyric: &mut Lyric) {
+    let offset = lyric.metadata.offset;
+    if offset == 0 {
+        return;
+    }
+    let shift = |time: u64| (time as i128 - offset as i128).clamp(0, u64::MAX as i128) as u64;
+    for line in &mut lyric.lines {
+        line.start = shift(line.start);
+        for syllable in &mut line.syllables {
+            syllable.start = shift(syllable.start);
+        }
+    }
+    lyric.metadata.offset = 0;
+}
+
+#[derive(Debug, Clone)]
+struct WordTag {
+    time: Option<u64>,
+    text: String,
+}
+
+fn parse_word_tags(text: &str) -> std::result::Result<Vec<WordTag>, (usize, String)> {
+    let mut words: Vec<WordTag> = Vec::new();
+    let mut pos = 0;
+    let mut search = 0;
+
+    while let Some(open) = text[search..].find('<').map(|i| search + i) {
+        let Some(close) = text[open..].find('>').map(|i| open + i) else { break };
+        let tag = &text[open + 1..close];
+        let Some(time) = parse_timestamp(tag) else {
+            if has_timestamp_shape(tag) {
+                return Err((open, tag.to_string()));
+            }
+            // 不是时间标签的尖括号（如 "<3"）保留为歌词文本
+            search = open + 1;
+            continue;
+        };
+
+        match words.last_mut() {
+            Some(last) => last.text.push_str(&text[pos..open]),
+            None if open > 0 => words.push(WordTag { time: None, text: text[..open].to_string() }),
+            None => {},
+        }
+        words.push(WordTag { time: Some(time), text: String::new() });
+        pos = close + 1;
+        search = pos;
+    }
+
+    if let Some(last) = words.last_mut() {
+        last.text.push_str(&text[pos..]);
+    }
+    Ok(words)
+}
+
+fn has_timestamp_shape(tag: &str) -> bool {
+    tag.split_once(':').is_some_and(|(minutes, rest)| {
+        !minutes.is_empty() && minutes.chars().all(|c| c.is_ascii_digit()) && rest.starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+pub(crate) fn parse_timestamp(tag: &str) -> Option<u64> {
+    let (minutes, rest) = tag.split_once(':')?;
+    let (seconds, fraction) = match rest.find(['.', ':']) {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None => (rest, ""),
+    };
+
+    if minutes.is_empty() || seconds.is_empty() || fraction.len() > 3 {
+        return None;
+    }
+    let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if !all_digits(minutes) || !all_digits(seconds) || !all_digits(fraction) {
+        return None;
+    }
+
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: u64 = seconds.parse().ok()?;
+    let millis = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<u64>().ok()? * 100,
+        2 => fraction.parse::<u64>().ok()? * 10,
+        _ => fraction.parse::<u64>().ok()?,
+    };
+    minutes.checked_mul(60_000)?.checked_add(seconds.checked_mul(1000)?)?.checked_add(millis)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimePrecision {
+    #[default]
+    Centisecond,
+    Millisecond,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnhancedLrcOptions {
+    pub precision: TimePrecision,
+    pub include_end_tag: bool,
+}
+
+impl Default for EnhancedLrcOptions {
+    fn default() -> Self {
+        EnhancedLrcOptions { precision: TimePrecision::Centisecond, include_end_tag: true }
+    }
+}
+
+pub fn format_timestamp(ms: u64) -> String {
+    format_timestamp_with(ms, TimePrecision::Centisecond)
+}
+
+pub fn format_timestamp_with(ms: u64, precision: TimePrecision) -> String {
+    match precision {
+        TimePrecision::Centisecond => format!("{:02}:{:02}.{:02}", ms / 60_000, ms % 60_000 / 1000, ms % 1000 / 10),
+        TimePrecision::Millisecond => format!("{:02}:{:02}.{:03}", ms / 60_000, ms % 60_000 / 1000, ms % 1000),
+    }
+}
+
+pub fn convert_qrc_to_lrc(qrc_content: &str) -> Result<String> {
+    Ok(write(&crate::qrc::parse(qrc_content)?))
+}
+
+pub fn has_usable_timing(content: &str) -> bool {
+    match parse(content) {
+        Ok(lyric) => match lyric.lines.first() {
+            Some(first) => lyric.lines.len() == 1 || lyric.lines.iter().any(|l| l.start != first.start),
+            None => false,
+        },
+        Err(_) => false,
+    }
+}
+
+// 补全 LRC 文本头部缺失或为空的标签，不重写歌词行
+pub fn fill_tags(content: &str, metadata: &LyricMetadata) -> String {
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let mut header_end = lines.iter()
+        .position(|line| !line.trim().is_empty() && split_tag(line.trim()).is_none())
+        .unwrap_or(lines.len());
+
+    for (key, value) in metadata.tags() {
+        if key == "offset" || value.is_empty() {
+            continue;
+        }
+        let existing = lines[..header_end].iter()
+            .position(|line| split_tag(line.trim()).is_some_and(|(k, _)| k.eq_ignore_ascii_case(&key)));
+        match existing {
+            Some(index) => {
+                if split_tag(lines[index].trim()).is_some_and(|(_, v)| v.trim().is_empty()) {
+                    lines[index] = format!("[{}:{}]", key, value);
+                }
+            },
+            None => {
+                lines.insert(header_end, format!("[{}:{}]", key, value));
+                header_end += 1;
+            },
+        }
+    }
+
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+pub fn write(lyric: &Lyric) -> String {
+    write_aligned(&AlignedLyric::from(lyric.clone()))
+}
+
+pub fn write_aligned(aligned: &AlignedLyric) -> String {
+    let mut output = String::new();
+    for (key, value) in aligned.metadata.tags() {
+        output.push_str(&format!("[{}:{}]\n", key, value));
+    }
+
+    for line in &aligned.lines {
+        let timestamp = format_timestamp(line.original.start);
+        output.push_str(&format!("[{}]{}\n", timestamp, line.original.text()));
+        for extra in [&line.translation, &line.romanization].into_iter().flatten() {
+            output.push_str(&format!("[{}]{}\n", timestamp, extra.text()));
+        }
+    }
+    output
+}
+
+pub fn write_enhanced(lyric: &Lyric, options: &EnhancedLrcOptions) -> String {
+    let mut output = String::new();
+    for (key, value) in lyric.metadata.tags() {
+        output.push_str(&format!("[{}:{}]\n", key, value));
+    }
+
+    for line in &lyric.lines {
+        output.push_str(&format!("[{}]", format_timestamp_with(line.start, options.precision)));
+
+        let mut last_end = None;
+        for syllable in &line.syllables {
+            if syllable.text.trim().is_empty() {
+                output.push_str(&syllable.text);
+                continue;
+            }
+            output.push_str(&format!("<{}>{}", format_timestamp_with(syllable.start, options.precision), syllable.text));
+            last_end = Some(syllable.end());
+        }
+
+        if let Some(end) = last_end.filter(|_| options.include_end_tag) {
+            output.push_str(&format!("<{}>", format_timestamp_with(end, options.precision)));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lyric: &Lyric) -> Vec<(u64, String)> {
+        lyric.lines.iter().map(|line| (line.start, line.text())).collect()
+    }
+
+    #[test]
+    fn parses_tags_and_multiple_timestamps() {
+        let lyric = parse("[ti:晴天]\n[ar:周杰伦]\n[al:叶惠美]\n[by:someone]\n[00:01.00][00:05.50]副歌\n[00:03.00]主歌\n").unwrap();
+
+        assert_eq!(lyric.metadata.title.as_deref(), Some("晴天"));
+        assert_eq!(lyric.metadata.artist.as_deref(), Some("周杰伦"));
+        assert_eq!(lyric.metadata.album.as_deref(), Some("叶惠美"));
+        assert_eq!(lyric.metadata.by.as_deref(), Some("someone"));
+        assert_eq!(texts(&lyric), vec![(1000, "副歌".to_string()), (3000, "主歌".to_string()), (5500, "副歌".to_string())]);
+        assert_eq!(lyric.lines[0].duration, 2000);
+    }
+
+    #[test]
+    fn applies_offset() {
+        let lyric = parse("[offset:+500]\n[00:01.00]a\n[00:00.20]b\n").unwrap();
+
+        assert_eq!(lyric.metadata.offset, 0);
+        assert_eq!(texts(&lyric), vec![(0, "b".to_string()), (500, "a".to_string())]);
+    }
+
+    #[test]
+    fn parses_word_tags() {
+        let lyric = parse("[00:01.00]<00:01.00>歌<00:01.50>词<00:02.00>\n").unwrap();
+        let line = &lyric.lines[0];
+
+        assert_eq!(line.start, 1000);
+        assert_eq!(line.duration, 1000);
+        assert_eq!(line.syllables, vec![
+            Syllable { text: "歌".to_string(), start: 1000, duration: 500 },
+            Syllable { text: "词".to_string(), start: 1500, duration: 500 },
+        ]);
+    }
+
+    #[test]
+    fn keeps_text_before_first_word_tag() {
+        let lyric = parse("[00:01.00]歌<00:01.50>词<00:02.00>\n").unwrap();
+
+        assert_eq!(lyric.lines[0].syllables, vec![
+            Syllable { text: "歌".to_string(), start: 1000, duration: 500 },
+            Syllable { text: "词".to_string(), start: 1500, duration: 500 },
+        ]);
+    }
+
+    #[test]
+    fn keeps_non_timestamp_angle_brackets_as_text() {
+        let lyric = parse("[00:01.00]I <3> you\n[00:02.00]<00:02.00>a <3 <00:02.50>b\n").unwrap();
+
+        assert_eq!(texts(&lyric), vec![(1000, "I <3> you".to_string()), (2000, "a <3 b".to_string())]);
+    }
+
+    #[test]
+    fn handles_out_of_range_times() {
+        let lyric = parse("[99999999999999999:00.00]a\n[00:01.00]b\n").unwrap();
+        assert_eq!(texts(&lyric), vec![(1000, "b".to_string())]);
+
+        let lyric = parse("[offset:-9223372036854775808]\n[00:01.00]a\n").unwrap();
+        assert_eq!(lyric.lines[0].start, 9_223_372_036_854_776_808);
+
+        let lyric = parse("[offset:9223372036854775807]\n[00:01.00]a\n").unwrap();
+        assert_eq!(lyric.lines[0].start, 0);
+    }
+
+    #[test]
+    fn keeps_non_timestamp_brackets_as_text() {
+        let lyric = parse("[00:01.00][Chorus] la\n[00:02.00][00:03.00\n").unwrap();
+
+        assert_eq!(texts(&lyric), vec![(1000, "[Chorus] la".to_string()), (2000, "[00:03.00".to_string())]);
+    }
+
+    #[test]
+    fn skips_untimed_lines() {
+        let lyric = parse("作词: 某人\n[00:01.00]a\n纯文本\n[00:02.00]b\n").unwrap();
+
+        assert_eq!(texts(&lyric), vec![(1000, "a".to_string()), (2000, "b".to_string())]);
+    }
+
+    #[test]
+    fn reports_invalid_word_tag_column() {
+        match parse("[00:01.00]<00:01.00>a<00:0x>b") {
+            Err(AppError::LyricParse { line, column, .. }) => assert_eq!((line, column), (1, 22)),
+            other => panic!("应返回解析错误: {:?}", other),
+        }
+    }
+
+    fn enhanced_sample() -> Lyric {
+        let syllables = vec![
+            Syllable { text: "歌".to_string(), start: 1234, duration: 500 },
+            Syllable { text: " ".to_string(), start: 1734, duration: 0 },
+            Syllable { text: "词".to_string(), start: 1734, duration: 567 },
+        ];
+        Lyric { lines: vec![LyricLine { start: 1234, duration: 1067, syllables }], ..Lyric::default() }
+    }
+
+    #[test]
+    fn writes_enhanced_with_precision() {
+        let lyric = enhanced_sample();
+
+        let centi = write_enhanced(&lyric, &EnhancedLrcOptions { precision: TimePrecision::Centisecond, include_end_tag: false });
+        assert_eq!(centi, "[00:01.23]<00:01.23>歌 <00:01.73>词\n");
+
+        let milli = write_enhanced(&lyric, &EnhancedLrcOptions { precision: TimePrecision::Millisecond, include_end_tag: false });
+        assert_eq!(milli, "[00:01.234]<00:01.234>歌 <00:01.734>词\n");
+    }
+
+    #[test]
+    fn writes_enhanced_end_tag() {
+        let lyric = enhanced_sample();
+
+        let with_end = write_enhanced(&lyric, &EnhancedLrcOptions { precision: TimePrecision::Millisecond, include_end_tag: true });
+        assert_eq!(with_end, "[00:01.234]<00:01.234>歌 <00:01.734>词<00:02.301>\n");
+
+        // 结束标签回读后成为最后一个字的结束时间
+        let reparsed = parse(&with_end).unwrap();
+        assert_eq!(reparsed.lines[0].syllables.last().map(Syllable::end), Some(2301));
+    }
+
+}
@@ -0,0 +1,102 @@
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lyric {
+    pub metadata: LyricMetadata,
+    pub lines: Vec<LyricLine>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LyricMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub by: Option<String>,
+    pub offset: i64,
+    pub extra: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LyricLine {
+    pub start: u64,
+    pub duration: u64,
+    pub syllables: Vec<Syllable>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Syllable {
+    pub text: String,
+    pub start: u64,
+    pub duration: u64,
+}
+
+impl LyricMetadata {
+    pub fn set_tag(&mut self, key: &str, value: &str) {
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "ti" => self.title = Some(value.to_string()),
+            "ar" => self.artist = Some(value.to_string()),
+            "al" => self.album = Some(value.to_string()),
+            "by" => self.by = Some(value.to_string()),
+            "offset" => self.offset = value.parse().unwrap_or(0),
+            other => self.extra.push((other.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn merge_missing(&mut self, other: &LyricMetadata) {
+        let fields = [
+            (&mut self.title, &other.title),
+            (&mut self.artist, &other.artist),
+            (&mut self.album, &other.album),
+            (&mut self.by, &other.by),
+        ];
+        for (field, value) in fields {
+            if field.as_deref().is_none_or(str::is_empty) && value.as_deref().is_some_and(|v| !v.is_empty()) {
+                *field = value.clone();
+            }
+        }
+        for (key, value) in &other.extra {
+            if !self.extra.iter().any(|(k, _)| k == key) {
+                self.extra.push((key.clone(), value.clone()));
+            }
+        }
+    }
+
+    pub fn tags(&self) -> Vec<(String, String)> {
+        let mut tags = Vec::new();
+        let known = [("ti", &self.title), ("ar", &self.artist), ("al", &self.album), ("by", &self.by)];
+        for (key, value) in known {
+            if let Some(value) = value {
+                tags.push((key.to_string(), value.clone()));
+            }
+        }
+        if self.offset != 0 {
+            tags.push(("offset".to_string(), self.offset.to_string()));
+        }
+        tags.extend(self.extra.iter().cloned());
+        tags
+    }
+}
+
+impl LyricLine {
+    pub fn end(&self) -> u64 {
+        self.start.saturating_add(self.duration)
+    }
+
+    pub fn text(&self) -> String {
+        self.syllables.iter().map(|s| s.text.as_str()).collect()
+    }
+}
+
+impl Syllable {
+    pub fn end(&self) -> u64 {
+        self.start.saturating_add(self.duration)
+    }
+}
+
+pub(crate) fn split_tag(line: &str) -> Option<(&str, &str)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (key, value) = inner.split_once(':')?;
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Some((key, value))
+}
@@ -0,0 +1,318 @@
+use once_cell::sync::Lazy;
+use quick_xml::{Reader, escape::escape, events::Event};
+use regex::Regex;
+
+use crate::align::AlignedLyric;
+use crate::lyric::{Lyric, LyricLine, Syllable, split_tag};
+use crate::{AppError, Result};
+
+static LYRIC_CONTENT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"LyricContent="([\s\S]*?)"\s*/>"#).expect("未能编译歌词内容正则表达式")
+});
+static LYRIC_CONTENT_START_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"LyricContent\s*=\s*["']"#).expect("未能编译歌词内容位置正则表达式")
+});
+static WORD_TIME_TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\((\d+),(\d+)\)").expect("未能编译词时间正则表达式")
+});
+
+pub fn parse(content: &str) -> Result<Lyric> {
+    let content = content.trim_start_matches('\u{feff}');
+    if content.trim_start().starts_with('<') {
+        let lyric_content = extract_lyric_content(content)?;
+        parse_lines(&lyric_content).map_err(|e| match e {
+            // 行列号换算成相对整个文件，而不是 LyricContent 属性值
+            AppError::LyricParse { line, column, message } => {
+                let (line, column) = file_position(content, &lyric_content, line, column);
+                AppError::LyricParse { line, column, message }
+            },
+            e => e,
+        })
+    } else {
+        parse_lines(content)
+    }
+}
+
+pub fn extract_lyric_content(xml: &str) -> Result<String> {
+    match read_lyric_content_attr(xml) {
+        Ok(content) => Ok(content),
+        Err(AppError::XmlParse(e)) => LYRIC_CONTENT_REGEX.captures(xml)
+            .map(|caps| caps[1].to_string())
+            .ok_or(AppError::XmlParse(e)),
+        Err(e) => Err(e),
+    }
+}
+
+// 逐字符对照原文件中的属性值，实体 (如 &amp;) 反转义后只占一个字符，但在原文件中占多列
+fn file_position(xml: &str, content: &str, line: usize, column: usize) -> (usize, usize) {
+    let Some(start) = LYRIC_CONTENT_START_REGEX.find(xml).map(|m| m.end()) else {
+        return (line, column);
+    };
+    let quote = xml[..start].chars().last().unwrap_or('"');
+    let raw = &xml[start..];
+    let raw = &raw[..raw.find(quote).unwrap_or(raw.len())];
+    // 正则兜底时拿到的是未反转义的原文
+    let escaped = raw != content;
+
+    let before = &xml[..start];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let (mut raw_line, mut raw_column) = (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1);
+    let (mut current_line, mut current_column) = (1, 1);
+    let mut rest = raw;
+
+    while let Some(c) = rest.chars().next() {
+        if (current_line, current_column) == (line, column) {
+            break;
+        }
+        let (len, decoded) = match c {
+            '&' if escaped => decode_entity(rest).unwrap_or((1, '&')),
+            c => (c.len_utf8(), c),
+        };
+        for raw_char in rest[..len].chars() {
+            if raw_char == '\n' {
+                raw_line += 1;
+                raw_column = 1;
+            } else {
+                raw_column += 1;
+            }
+        }
+        if decoded == '\n' {
+            current_line += 1;
+            current_column = 1;
+        } else {
+            current_column += 1;
+        }
+        rest = &rest[len..];
+    }
+
+    (raw_line, raw_column)
+}
+
+fn decode_entity(text: &str) -> Option<(usize, char)> {
+    let semicolon = text.get(..12).unwrap_or(text).find(';')?;
+    let decoded = match &text[1..semicolon] {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        name => {
+            let code = match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => name.strip_prefix('#')?.parse().ok()?,
+            };
+            char::from_u32(code)?
+        },
+    };
+    Some((semicolon + 1, decoded))
+}
+
+fn read_lyric_content_attr(xml: &str) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) => {
+                for attr in e.attributes() {
+                    let attr = attr.map_err(quick_xml::Error::from)?;
+                    if attr.key.as_ref() == b"LyricContent" {
+                        return Ok(attr.unescape_value()?.into_owned());
+                    }
+                }
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+
+    Err(AppError::LyricNotFound)
+}
+
+fn parse_lines(content: &str) -> Result<Lyric> {
+    let mut lyric = Lyric::default();
+
+    for (index, raw_line) in content.lines().enumerate() {
+        let line_no = index + 1;
+        let line = raw_line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some((key, value)) = split_tag(line.trim()) {
+            lyric.metadata.set_tag(key, value);
+            continue;
+        }
+
+        lyric.lines.push(parse_line(line, line_no)?);
+    }
+
+    Ok(lyric)
+}
+
+fn parse_line(line: &str, line_no: usize) -> Result<LyricLine> {
+    let error = |byte_pos: usize, message: &str| AppError::LyricParse {
+        line: line_no,
+        column: line[..byte_pos].chars().count() + 1,
+        message: message.to_string(),
+    };
+
+    if !line.starts_with('[') {
+        return Err(error(0, "行首缺少 [开始时间,持续时间] 标签"));
+    }
+    let close = line.find(']').ok_or_else(|| error(0, "行时间标签缺少 ']'"))?;
+    let (start_str, duration_str) = line[1..close].split_once(',')
+        .ok_or_else(|| error(1, "行时间标签应为 [开始时间,持续时间]"))?;
+    let start = start_str.trim().parse::<u64>()
+        .map_err(|_| error(1, &format!("无效的开始时间 '{}'", start_str)))?;
+    let duration = duration_str.trim().parse::<u64>()
+        .map_err(|_| error(2 + start_str.len(), &format!("无效的持续时间 '{}'", duration_str)))?;
+
+    let content_offset = close + 1;
+    let content = &line[content_offset..];
+    let mut syllables = Vec::new();
+    let mut word_start = 0;
+
+    for cap in WORD_TIME_TAG_REGEX.captures_iter(content) {
+        let tag = cap.get(0).unwrap();
+        let word_start_ms = cap[1].parse::<u64>()
+            .map_err(|_| error(content_offset + tag.start() + 1, "词开始时间超出范围"))?;
+        let word_duration_ms = cap[2].parse::<u64>()
+            .map_err(|_| error(content_offset + cap.get(2).unwrap().start(), "词持续时间超出范围"))?;
+
+        syllables.push(Syllable {
+            text: content[word_start..tag.start()].to_string(),
+            start: word_start_ms,
+            duration: word_duration_ms,
+        });
+        word_start = tag.end();
+    }
+
+    let trailing = &content[word_start..];
+    if syllables.is_empty() {
+        syllables.push(Syllable { text: trailing.to_string(), start, duration });
+    } else if !trailing.trim().is_empty() {
+        let last_end = syllables.last().map_or(start, Syllable::end);
+        syllables.push(Syllable { text: trailing.to_string(), start: last_end, duration: 0 });
+    }
+
+    Ok(LyricLine { start, duration, syllables })
+}
+
+pub fn write(lyric: &Lyric) -> String {
+    write_aligned(&AlignedLyric::from(lyric.clone()))
+}
+
+pub fn write_aligned(aligned: &AlignedLyric) -> String {
+    let mut content = String::new();
+    for (key, value) in aligned.metadata.tags() {
+        content.push_str(&format!("[{}:{}]\n", key, value));
+    }
+
+    for line in &aligned.lines {
+        content.push_str(&format_line(&line.original));
+        if let Some(translation) = &line.translation {
+            let text = translation.text();
+            content.push_str(&format!("[{},{}]{}({},{})\n", line.original.start, line.original.duration, text, line.original.start, line.original.duration));
+        }
+        if let Some(romanization) = &line.romanization {
+            content.push_str(&format_line(romanization));
+        }
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<QrcInfos>\n<QrcHeadInfo SaveTime=\"0\" Version=\"100\"/>\n<LyricInfo LyricCount=\"1\">\n<Lyric_1 LyricType=\"1\" LyricContent=\"{}\"/>\n</LyricInfo>\n</QrcInfos>\n",
+        escape(&content)
+    )
+}
+
+fn format_line(line: &LyricLine) -> String {
+    let mut output = format!("[{},{}]", line.start, line.duration);
+    for syllable in &line.syllables {
+        output.push_str(&format!("{}({},{})", syllable.text, syllable.start, syllable.duration));
+    }
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::{self, ConvertOptions, LyricFormat};
+
+    const HEAD: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<QrcInfos>\n<QrcHeadInfo SaveTime=\"0\" Version=\"100\"/>\n<LyricInfo LyricCount=\"1\">\n<Lyric_1 LyricType=\"1\" LyricContent=\"";
+    const TAIL: &str = "\"/>\n</LyricInfo>\n</QrcInfos>\n";
+
+    fn wrap(content: &str) -> String {
+        format!("{}{}{}", HEAD, content, TAIL)
+    }
+
+    fn parse_error(content: &str) -> (usize, usize) {
+        match parse(content) {
+            Err(AppError::LyricParse { line, column, .. }) => (line, column),
+            other => panic!("应返回解析错误: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_header_tags_and_syllables() {
+        let lyric = parse("[ti:晴天]\n[ar:周杰伦]\n[offset:-200]\n[1000,2000]歌(1000,500)词(1500,1500)\n").unwrap();
+
+        assert_eq!(lyric.metadata.title.as_deref(), Some("晴天"));
+        assert_eq!(lyric.metadata.artist.as_deref(), Some("周杰伦"));
+        assert_eq!(lyric.metadata.offset, -200);
+        assert_eq!(lyric.lines.len(), 1);
+        assert_eq!(lyric.lines[0].start, 1000);
+        assert_eq!(lyric.lines[0].duration, 2000);
+        assert_eq!(lyric.lines[0].text(), "歌词");
+        assert_eq!(lyric.lines[0].syllables[1], Syllable { text: "词".to_string(), start: 1500, duration: 1500 });
+    }
+
+    #[test]
+    fn parses_xml_wrapper() {
+        let lyric = parse(&wrap("[ti:晴天]\n[1000,1000]a &amp; b(1000,1000)\n[2000,500]c(2000,500)\n")).unwrap();
+
+        assert_eq!(lyric.metadata.title.as_deref(), Some("晴天"));
+        assert_eq!(lyric.lines.len(), 2);
+        assert_eq!(lyric.lines[0].text(), "a & b");
+        assert_eq!(lyric.lines[1].start, 2000);
+    }
+
+    #[test]
+    fn round_trips_through_write() {
+        let lyric = parse("[ti:晴天]\n[1000,2000]歌(1000,500)词(1500,1500)\n").unwrap();
+        assert_eq!(parse(&write(&lyric)).unwrap(), lyric);
+    }
+
+    #[test]
+    fn converts_extreme_times_without_overflow() {
+        let lyric = parse("[0,0]a(18446744073709551615,5)\n[18446744073709551615,18446744073709551615]b\n").unwrap();
+        assert_eq!(lyric.lines[0].syllables[0].end(), u64::MAX);
+        assert_eq!(lyric.lines[1].end(), u64::MAX);
+
+        let aligned = AlignedLyric::from(lyric);
+        for format in [LyricFormat::Lrc, LyricFormat::EnhancedLrc, LyricFormat::Qrc, LyricFormat::Ass, LyricFormat::Srt, LyricFormat::WebVtt, LyricFormat::Ttml] {
+            convert::write_aligned(&mut Vec::new(), &aligned, format, &ConvertOptions::default()).unwrap();
+        }
+    }
+
+    #[test]
+    fn reports_error_columns() {
+        assert_eq!(parse_error("[1000,2000]a(1000,2000)\n歌词"), (2, 1));
+        assert_eq!(parse_error("[1000,2000]a(1000,2000)\n[1000,x]b"), (2, 7));
+        assert_eq!(parse_error("[abc,2000]a"), (1, 2));
+        assert_eq!(parse_error("[1000,2000"), (1, 1));
+        assert_eq!(parse_error("[0,0]a(99999999999999999999,1)"), (1, 8));
+    }
+
+    #[test]
+    fn reports_error_lines_relative_to_file() {
+        let content_start = HEAD.lines().count();
+        let column_offset = HEAD.rsplit('\n').next().unwrap().chars().count();
+
+        assert_eq!(parse_error(&wrap("[ti:晴天]\n[1000,x]b\n")), (content_start + 1, 7));
+        assert_eq!(parse_error(&wrap("[1000,x]b\n")), (content_start, column_offset + 7));
+        assert_eq!(parse_error(&wrap("[0,1]a&amp;b(99999999999999999999,1)")), (content_start, column_offset + 14));
+        assert_eq!(parse_error(&wrap("[ti:&quot;a&quot;]&#10;[0,x]b")), (content_start, column_offset + 27));
+        assert_eq!(parse_error(&wrap("[0,1]&apos;&lt;(0,1)x\n[1,x]")), (content_start + 1, 4));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::align::{AlignedLine, AlignedLyric};
+
+const DEFAULT_CUE_DURATION_MS: u64 = 5000;
+
+fn format_time(ms: u64, separator: char) -> String {
+    format!("{:02}:{:02}:{:02}{}{:03}", ms / 3_600_000, ms % 3_600_000 / 60_000, ms % 60_000 / 1000, separator, ms % 1000)
+}
+
+fn cue_range(line: &AlignedLine) -> (u64, u64) {
+    let start = line.original.start;
+    let end = if line.original.duration == 0 { start + DEFAULT_CUE_DURATION_MS } else { line.original.end() };
+    (start, end)
+}
+
+pub fn write_srt(aligned: &AlignedLyric) -> String {
+    let mut output = String::new();
+    let mut index = 0;
+
+    for line in &aligned.lines {
+        let text = line.original.text();
+        if text.trim().is_empty() {
+            continue;
+        }
+        index += 1;
+        let (start, end) = cue_range(line);
+        output.push_str(&format!("{}\n{} --> {}\n{}\n", index, format_time(start, ','), format_time(end, ','), text.trim()));
+        if let Some(translation) = &line.translation {
+            output.push_str(&format!("{}\n", translation.text().trim()));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+pub fn write_webvtt(aligned: &AlignedLyric, karaoke: bool) -> String {
+    let mut output = String::from("WEBVTT\n\n");
+
+    for line in &aligned.lines {
+        if line.original.text().trim().is_empty() {
+            continue;
+        }
+        let (start, end) = cue_range(line);
+        output.push_str(&format!("{} --> {}\n", format_time(start, '.'), format_time(end, '.')));
+
+        if karaoke {
+            for syllable in &line.original.syllables {
+                if syllable.start > start && syllable.start < end && !syllable.text.trim().is_empty() {
+                    output.push_str(&format!("<{}>", format_time(syllable.start, '.')));
+                }
+                output.push_str(&escape_vtt(&syllable.text));
+            }
+            output.push('\n');
+        } else {
+            output.push_str(&format!("{}\n", escape_vtt(line.original.text().trim())));
+        }
+
+        if let Some(translation) = &line.translation {
+            output.push_str(&format!("{}\n", escape_vtt(translation.text().trim())));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lyric::{LyricLine, LyricMetadata, Syllable};
+
+    fn line(start: u64, duration: u64, parts: &[(&str, u64, u64)]) -> LyricLine {
+        let syllables = parts.iter().map(|&(text, start, duration)| Syllable { text: text.to_string(), start, duration }).collect();
+        LyricLine { start, duration, syllables }
+    }
+
+    fn aligned(lines: Vec<(LyricLine, Option<LyricLine>)>) -> AlignedLyric {
+        let lines = lines.into_iter().map(|(original, translation)| AlignedLine { original, translation, romanization: None }).collect();
+        AlignedLyric { metadata: LyricMetadata::default(), lines }
+    }
+
+    #[test]
+    fn numbers_and_times_srt_cues() {
+        let lyric = aligned(vec![
+            (line(1000, 2500, &[("第一句", 1000, 2500)]), Some(line(1000, 2500, &[("first", 1000, 2500)]))),
+            (line(3500, 0, &[(" ", 3500, 0)]), None),
+            (line(3_723_004, 0, &[("第二句", 3_723_004, 0)]), None),
+        ]);
+
+        assert_eq!(
+            write_srt(&lyric),
+            "1\n00:00:01,000 --> 00:00:03,500\n第一句\nfirst\n\n2\n01:02:03,004 --> 01:02:08,004\n第二句\n\n"
+        );
+    }
+
+    #[test]
+    fn writes_webvtt_with_translation_and_escaping() {
+        let lyric = aligned(vec![(
+            line(1000, 1500, &[("Rock", 1000, 500), (" ", 1500, 0), ("&", 1500, 500), (" ", 2000, 0), ("<3", 2000, 500)]),
+            Some(line(1000, 1500, &[("a & b", 1000, 1500)])),
+        )]);
+
+        assert_eq!(write_webvtt(&lyric, false), "WEBVTT\n\n00:00:01.000 --> 00:00:02.500\nRock &amp; &lt;3\na &amp; b\n\n");
+    }
+
+    #[test]
+    fn writes_webvtt_karaoke_stamps() {
+        let lyric = aligned(vec![(
+            line(1000, 1500, &[("Rock", 1000, 500), (" ", 1500, 0), ("&", 1500, 500), (" ", 2000, 0), ("<3", 2000, 500)]),
+            None,
+        )]);
+
+        assert_eq!(
+            write_webvtt(&lyric, true),
+            "WEBVTT\n\n00:00:01.000 --> 00:00:02.500\nRock <00:00:01.500>&amp; <00:00:02.000>&lt;3\n\n"
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use quick_xml::Writer;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use std::io::Cursor;
+
+use crate::Result;
+use crate::align::AlignedLyric;
+use crate::lyric::{LyricLine, Syllable};
+
+const TRANSLATION_LANGUAGE: &str = "zh-Hans";
+const ROMANIZATION_LANGUAGE: &str = "und-Latn";
+
+fn format_time(ms: u64) -> String {
+    format!("{:02}:{:02}:{:02}.{:03}", ms / 3_600_000, ms % 3_600_000 / 60_000, ms % 60_000 / 1000, ms % 1000)
+}
+
+fn line_key(index: usize) -> String {
+    format!("L{}", index + 1)
+}
+
+pub fn write_ttml(aligned: &AlignedLyric) -> Result<String> {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let end = aligned.lines.iter().map(|l| l.original.end()).max().unwrap_or(0);
+
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("utf-8"), None)))?;
+    newline(&mut writer)?;
+
+    let mut tt = BytesStart::new("tt");
+    tt.push_attribute(("xmlns", "http://www.w3.org/ns/ttml"));
+    tt.push_attribute(("xmlns:ttm", "http://www.w3.org/ns/ttml#metadata"));
+    tt.push_attribute(("xmlns:itunes", "http://music.apple.com/lyric-ttml-internal"));
+    tt.push_attribute(("itunes:timing", "Word"));
+    writer.write_event(Event::Start(tt))?;
+    newline(&mut writer)?;
+
+    write_head(&mut writer, aligned)?;
+
+    let mut body = BytesStart::new("body");
+    body.push_attribute(("dur", format_time(end).as_str()));
+    writer.write_event(Event::Start(body))?;
+    newline(&mut writer)?;
+
+    let start = aligned.lines.iter().map(|l| l.original.start).min().unwrap_or(0);
+    let mut div = BytesStart::new("div");
+    div.push_attribute(("begin", format_time(start).as_str()));
+    div.push_attribute(("end", format_time(end).as_str()));
+    writer.write_event(Event::Start(div))?;
+    newline(&mut writer)?;
+
+    for (index, line) in aligned.lines.iter().enumerate() {
+        let mut p = BytesStart::new("p");
+        p.push_attribute(("begin", format_time(line.original.start).as_str()));
+        p.push_attribute(("end", format_time(line.original.end()).as_str()));
+        p.push_attribute(("itunes:key", line_key(index).as_str()));
+        p.push_attribute(("ttm:agent", "v1"));
+        writer.write_event(Event::Start(p))?;
+        write_syllables(&mut writer, &line.original)?;
+        writer.write_event(Event::End(BytesEnd::new("p")))?;
+        newline(&mut writer)?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("div")))?;
+    newline(&mut writer)?;
+    writer.write_event(Event::End(BytesEnd::new("body")))?;
+    newline(&mut writer)?;
+    writer.write_event(Event::End(BytesEnd::new("tt")))?;
+    newline(&mut writer)?;
+
+    Ok(String::from_utf8(writer.into_inner().into_inner())?)
+}
+
+fn write_head(writer: &mut Writer<Cursor<Vec<u8>>>, aligned: &AlignedLyric) -> Result<()> {
+    writer.write_event(Event::Start(BytesStart::new("head")))?;
+    writer.write_event(Event::Start(BytesStart::new("metadata")))?;
+
+    let mut agent = BytesStart::new("ttm:agent");
+    agent.push_attribute(("type", "person"));
+    agent.push_attribute(("xml:id", "v1"));
+    writer.write_event(Event::Empty(agent))?;
+
+    if let Some(title) = &aligned.metadata.title {
+        writer.create_element("ttm:title").write_text_content(BytesText::new(title))?;
+    }
+
+    let has_translation = aligned.lines.iter().any(|l| l.translation.is_some());
+    let has_romanization = aligned.lines.iter().any(|l| l.romanization.is_some());
+    if has_translation || has_romanization {
+        let mut itunes = BytesStart::new("iTunesMetadata");
+        itunes.push_attribute(("xmlns", "http://music.apple.com/lyric-ttml-internal"));
+        writer.write_event(Event::Start(itunes))?;
+
+        if has_translation {
+            writer.write_event(Event::Start(BytesStart::new("translations")))?;
+            let mut translation = BytesStart::new("translation");
+            translation.push_attribute(("type", "subtitle"));
+            translation.push_attribute(("xml:lang", TRANSLATION_LANGUAGE));
+            writer.write_event(Event::Start(translation))?;
+            for (index, line) in aligned.lines.iter().enumerate() {
+                if let Some(translated) = &line.translation {
+                    writer.create_element("text")
+                        .with_attribute(("for", line_key(index).as_str()))
+                        .write_text_content(BytesText::new(translated.text().trim()))?;
+                }
+            }
+            writer.write_event(Event::End(BytesEnd::new("translation")))?;
+            writer.write_event(Event::End(BytesEnd::new("translations")))?;
+        }
+
+        if has_romanization {
+            writer.write_event(Event::Start(BytesStart::new("transliterations")))?;
+            let mut transliteration = BytesStart::new("transliteration");
+            transliteration.push_attribute(("xml:lang", ROMANIZATION_LANGUAGE));
+            writer.write_event(Event::Start(transliteration))?;
+            for (index, line) in aligned.lines.iter().enumerate() {
+                if let Some(romanized) = &line.romanization {
+                    let mut text = BytesStart::new("text");
+                    text.push_attribute(("for", line_key(index).as_str()));
+                    writer.write_event(Event::Start(text))?;
+                    write_syllables(writer, romanized)?;
+                    writer.write_event(Event::End(BytesEnd::new("text")))?;
+                }
+            }
+            writer.write_event(Event::End(BytesEnd::new("transliteration")))?;
+            writer.write_event(Event::End(BytesEnd::new("transliterations")))?;
+        }
+
+        writer.write_event(Event::End(BytesEnd::new("iTunesMetadata")))?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new("metadata")))?;
+    writer.write_event(Event::End(BytesEnd::new("head")))?;
+    newline(writer)?;
+    Ok(())
+}
+
+fn write_syllables(writer: &mut Writer<Cursor<Vec<u8>>>, line: &LyricLine) -> Result<()> {
+    let mut background: Option<Vec<&Syllable>> = None;
+
+    for syllable in &line.syllables {
+        let text = syllable.text.trim();
+        if let Some(group) = background.as_mut() {
+            group.push(syllable);
+            if text.ends_with([')', '）']) {
+                write_background(writer, group)?;
+                background = None;
+            }
+        } else if text.starts_with(['(', '（']) && !text.ends_with([')', '）']) {
+            background = Some(vec![syllable]);
+        } else if text.starts_with(['(', '（']) {
+            write_background(writer, &[syllable])?;
+        } else {
+            write_span(writer, syllable)?;
+        }
+    }
+
+    if let Some(group) = background {
+        for syllable in group {
+            write_span(writer, syllable)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_background(writer: &mut Writer<Cursor<Vec<u8>>>, group: &[&Syllable]) -> Result<()> {
+    let start = group.first().map_or(0, |s| s.start);
+    let end = group.iter().map(|s| s.end()).max().unwrap_or(start);
+
+    let mut span = BytesStart::new("span");
+    span.push_attribute(("ttm:role", "x-bg"));
+    span.push_attribute(("begin", format_time(start).as_str()));
+    span.push_attribute(("end", format_time(end).as_str()));
+    writer.write_event(Event::Start(span))?;
+    for syllable in group {
+        write_span(writer, syllable)?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("span")))?;
+    Ok(())
+}
+
+fn write_span(writer: &mut Writer<Cursor<Vec<u8>>>, syllable: &Syllable) -> Result<()> {
+    let text = syllable.text.trim();
+    if text.is_empty() {
+        if !syllable.text.is_empty() {
+            writer.write_event(Event::Text(BytesText::new(" ")))?;
+        }
+        return Ok(());
+    }
+
+    if syllable.text.starts_with(char::is_whitespace) {
+        writer.write_event(Event::Text(BytesText::new(" ")))?;
+    }
+    writer.create_element("span")
+        .with_attribute(("begin", format_time(syllable.start).as_str()))
+        .with_attribute(("end", format_time(syllable.end()).as_str()))
+        .write_text_content(BytesText::new(text))?;
+    if syllable.text.ends_with(char::is_whitespace) {
+        writer.write_event(Event::Text(BytesText::new(" ")))?;
+    }
+    Ok(())
+}
+
+fn newline(writer: &mut Writer<Cursor<Vec<u8>>>) -> Result<()> {
+    writer.write_event(Event::Text(BytesText::new("\n")))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::align::AlignedLine;
+    use crate::lyric::LyricMetadata;
+
+    fn line(parts: &[(&str, u64, u64)]) -> LyricLine {
+        let syllables: Vec<Syllable> = parts.iter().map(|&(text, start, duration)| Syllable { text: text.to_string(), start, duration }).collect();
+        let start = syllables.first().map_or(0, |s| s.start);
+        let end = syllables.iter().map(Syllable::end).max().unwrap_or(start);
+        LyricLine { start, duration: end - start, syllables }
+    }
+
+    fn aligned(lines: Vec<AlignedLine>) -> AlignedLyric {
+        AlignedLyric { metadata: LyricMetadata::default(), lines }
+    }
+
+    #[test]
+    fn pairs_translations_with_line_keys() {
+        let lyric = aligned(vec![
+            AlignedLine { original: line(&[("one", 1000, 500)]), translation: Some(line(&[("一", 1000, 500)])), romanization: None },
+            AlignedLine { original: line(&[("two", 2000, 500)]), translation: None, romanization: None },
+            AlignedLine { original: line(&[("three", 3000, 500)]), translation: Some(line(&[("三", 3000, 500)])), romanization: None },
+        ]);
+        let ttml = write_ttml(&lyric).unwrap();
+
+        assert!(ttml.contains(r#"<p begin="00:00:01.000" end="00:00:01.500" itunes:key="L1" ttm:agent="v1">"#));
+        assert!(ttml.contains(r#"<p begin="00:00:03.000" end="00:00:03.500" itunes:key="L3" ttm:agent="v1">"#));
+        assert!(ttml.contains(r#"<translation type="subtitle" xml:lang="zh-Hans"><text for="L1">一</text><text for="L3">三</text></translation>"#));
+        assert!(!ttml.contains("transliterations"));
+    }
+
+    #[test]
+    fn groups_parenthesized_syllables_as_background() {
+        let lyric = aligned(vec![AlignedLine {
+            original: line(&[("la ", 1000, 500), ("(oh ", 1500, 300), ("yeah)", 1800, 400), (" (hey)", 2200, 300)]),
+            translation: None,
+            romanization: None,
+        }]);
+        let ttml = write_ttml(&lyric).unwrap();
+
+        assert!(ttml.contains(concat!(
+            r#"<span begin="00:00:01.000" end="00:00:01.500">la</span> "#,
+            r#"<span ttm:role="x-bg" begin="00:00:01.500" end="00:00:02.200">"#,
+            r#"<span begin="00:00:01.500" end="00:00:01.800">(oh</span> <span begin="00:00:01.800" end="00:00:02.200">yeah)</span></span>"#,
+            r#"<span ttm:role="x-bg" begin="00:00:02.200" end="00:00:02.500"> <span begin="00:00:02.200" end="00:00:02.500">(hey)</span></span>"#,
+        )));
+    }
+
+    #[test]
+    fn writes_transliteration_syllables() {
+        let lyric = aligned(vec![AlignedLine {
+            original: line(&[("歌", 1000, 500), ("词", 1500, 500)]),
+            translation: None,
+            romanization: Some(line(&[("ge ", 1000, 500), ("ci", 1500, 500)])),
+        }]);
+        let ttml = write_ttml(&lyric).unwrap();
+
+        assert!(ttml.contains(concat!(
+            r#"<transliterations><transliteration xml:lang="und-Latn"><text for="L1">"#,
+            r#"<span begin="00:00:01.000" end="00:00:01.500">ge</span> <span begin="00:00:01.500" end="00:00:02.000">ci</span>"#,
+            r#"</text></transliteration></transliterations>"#,
+        )));
+        assert!(!ttml.contains("<translations>"));
+    }
+
+    #[test]
+    fn spans_div_over_earliest_and_latest_lines() {
+        let lyric = aligned(vec![
+            AlignedLine { original: line(&[("b", 3000, 1000)]), translation: None, romanization: None },
+            AlignedLine { original: line(&[("a", 1000, 500)]), translation: None, romanization: None },
+        ]);
+        let ttml = write_ttml(&lyric).unwrap();
+
+        assert!(ttml.contains(r#"<div begin="00:00:01.000" end="00:00:04.000">"#));
+    }
+}
@@ -36,17 +36,27 @@ pub fn resolve_resp_json(callback_sign: &str, val: &str) -> Result<String> {
        )))
 }
 
-pub fn create_safe_filename(song: &Song) -> String {
-    let sanitize = |s: &str| -> String {
-        s.chars()
-         .map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' { c } else { '_' })
-         .collect::<String>()
-         .split_whitespace()
-         .filter(|part| !part.is_empty())
-         .collect::<Vec<_>>()
-         .join("_")
-    };
+fn sanitize(s: &str) -> String {
+    s.chars()
+     .map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' { c } else { '_' })
+     .collect::<String>()
+     .split_whitespace()
+     .filter(|part| !part.is_empty())
+     .collect::<Vec<_>>()
+     .join("_")
+}
+
+pub fn create_safe_dirname(artist: &str, title: &str) -> String {
+    let safe_artist = sanitize(artist);
+    let safe_title = sanitize(title);
+    match (safe_artist.is_empty(), safe_title.is_empty()) {
+        (_, true) => "未命名".to_string(),
+        (true, false) => safe_title,
+        (false, false) => format!("{} - {}", safe_artist, safe_title),
+    }
+}
 
+pub fn create_safe_filename(song: &Song) -> String {
     let safe_song_name = sanitize(&song.name);
     let safe_artist_name = song.singer.iter()
                                .map(|s| sanitize(&s.name))